@@ -0,0 +1,363 @@
+use crate::datetime::parse_datetime;
+use crate::jsonld::jsonld_nodes;
+use crate::meta::html_meta;
+use crate::{parse_document, QueryOptions};
+use chrono::{DateTime, Duration, Utc};
+use kuchikiki::iter::NodeIterator;
+use kuchikiki::NodeRef;
+use serde_json::Value;
+
+const PUBLISHED: &str = "published";
+const MODIFIED: &str = "modified";
+
+/// JSON-LD properties with the kind of date and confidence they give
+const JSONLD_PROPERTIES: &[(&str, &str, f64)] = &[
+    ("datePosted", PUBLISHED, 0.95),
+    ("datePublished", PUBLISHED, 0.9),
+    ("dateCreated", PUBLISHED, 0.7),
+    ("uploadDate", PUBLISHED, 0.7),
+    ("dateModified", MODIFIED, 0.9),
+];
+
+/// Lowercased meta tag names with the kind of date and confidence they give
+const META_TAGS: &[(&str, &str, f64)] = &[
+    ("article:published_time", PUBLISHED, 0.9),
+    ("datepublished", PUBLISHED, 0.85),
+    ("dcterms.issued", PUBLISHED, 0.75),
+    ("dcterms.created", PUBLISHED, 0.7),
+    ("dc.date.issued", PUBLISHED, 0.75),
+    ("dc.date.created", PUBLISHED, 0.7),
+    ("dc.date", PUBLISHED, 0.7),
+    ("dcterms.date", PUBLISHED, 0.7),
+    ("pubdate", PUBLISHED, 0.6),
+    ("publish-date", PUBLISHED, 0.6),
+    ("date", PUBLISHED, 0.55),
+    ("article:modified_time", MODIFIED, 0.85),
+    ("og:updated_time", MODIFIED, 0.8),
+    ("datemodified", MODIFIED, 0.85),
+    ("dcterms.modified", MODIFIED, 0.75),
+    ("dc.date.modified", MODIFIED, 0.75),
+    ("last-modified", MODIFIED, 0.5),
+];
+
+/// Words before a relative date naming what happened
+const PUBLISHED_CUES: &[&str] = &["posted", "published", "listed", "added", "reposted"];
+const MODIFIED_CUES: &[&str] = &["updated", "modified", "edited", "refreshed"];
+
+/// A date found in a page
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateCandidate {
+    /// `published` or `modified`
+    pub kind: String,
+    pub value: DateTime<Utc>,
+    /// Where the date was found, such as `json-ld:datePosted`, `meta:article:published_time`,
+    /// `time` or `text`
+    pub source: String,
+    /// From 0 to 1
+    pub confidence: f64,
+}
+
+/// Publication and modification dates of a page
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageDates {
+    pub published: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub candidates: Vec<DateCandidate>,
+}
+
+fn candidate(kind: &str, value: DateTime<Utc>, source: String, confidence: f64) -> DateCandidate {
+    DateCandidate {
+        kind: kind.to_string(),
+        value,
+        source,
+        confidence,
+    }
+}
+
+fn jsonld_dates(value: &Value, candidates: &mut Vec<DateCandidate>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                jsonld_dates(value, candidates);
+            }
+        }
+        Value::Object(object) => {
+            for (property, kind, confidence) in JSONLD_PROPERTIES {
+                if let Some(date) = object
+                    .get(*property)
+                    .and_then(Value::as_str)
+                    .and_then(parse_datetime)
+                {
+                    let source = format!("json-ld:{}", property);
+                    candidates.push(candidate(kind, date, source, *confidence));
+                }
+            }
+            for value in object.values() {
+                jsonld_dates(value, candidates);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn meta_dates(html: &str, candidates: &mut Vec<DateCandidate>) {
+    let meta = html_meta(html);
+    for (name, kind, confidence) in META_TAGS {
+        let Some((_, values)) = meta.tags.iter().find(|(tag, _)| tag == name) else {
+            continue;
+        };
+        if let Some(date) = values.iter().find_map(|value| parse_datetime(value)) {
+            let source = format!("meta:{}", name);
+            candidates.push(candidate(kind, date, source, *confidence));
+        }
+    }
+}
+
+/// `<time datetime>` elements, more trusted when their markup says what the date is
+fn time_dates(document: &NodeRef, candidates: &mut Vec<DateCandidate>) {
+    for node in document.descendants() {
+        let Some(element) = node.as_element() else {
+            continue;
+        };
+        if &*element.name.local != "time" {
+            continue;
+        }
+        let attributes = element.attributes.borrow();
+        let Some(date) = attributes.get("datetime").and_then(parse_datetime) else {
+            continue;
+        };
+        let markup = [attributes.get("itemprop"), attributes.get("class")]
+            .iter()
+            .flatten()
+            .map(|value| value.to_ascii_lowercase())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (kind, confidence) = if markup.contains("datemodified")
+            || MODIFIED_CUES.iter().any(|cue| markup.contains(cue))
+        {
+            (MODIFIED, 0.7)
+        } else if markup.contains("datepublished")
+            || markup.contains("dateposted")
+            || attributes.contains("pubdate")
+        {
+            (PUBLISHED, 0.8)
+        } else {
+            (PUBLISHED, 0.6)
+        };
+        candidates.push(candidate(kind, date, "time".to_string(), confidence));
+    }
+}
+
+/// Length of one `unit` of a relative date, "3 days ago"
+fn unit_duration(unit: &str) -> Option<Duration> {
+    let unit = unit.strip_suffix('s').unwrap_or(unit);
+    match unit {
+        "second" | "sec" => Some(Duration::seconds(1)),
+        "minute" | "min" => Some(Duration::minutes(1)),
+        "hour" | "hr" | "h" => Some(Duration::hours(1)),
+        "day" | "d" => Some(Duration::days(1)),
+        "week" | "wk" | "w" => Some(Duration::weeks(1)),
+        "month" | "mo" => Some(Duration::days(30)),
+        "year" | "yr" | "y" => Some(Duration::days(365)),
+        _ => None,
+    }
+}
+
+/// Number of units of a relative date, "a", "one" or digits like "30+"
+fn count(word: &str) -> Option<i32> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        word => word
+            .trim_end_matches('+')
+            .parse()
+            .ok()
+            .filter(|count| (0..=10_000).contains(count)),
+    }
+}
+
+/// Kind of date named by a cue among the three words before a relative date
+fn cue(words: &[String]) -> Option<&'static str> {
+    words.iter().rev().take(3).find_map(|word| {
+        if PUBLISHED_CUES.contains(&word.as_str()) {
+            Some(PUBLISHED)
+        } else if MODIFIED_CUES.contains(&word.as_str()) {
+            Some(MODIFIED)
+        } else {
+            None
+        }
+    })
+}
+
+/// Relative dates such as "Posted 3 days ago", "30+ days ago" or "Updated yesterday",
+/// counted back from `anchor`
+fn text_dates(document: &NodeRef, anchor: DateTime<Utc>, candidates: &mut Vec<DateCandidate>) {
+    for node in document.descendants().text_nodes() {
+        let hidden = node.as_node().ancestors().any(|ancestor| {
+            ancestor.as_element().is_some_and(|element| {
+                matches!(
+                    &*element.name.local,
+                    "script" | "style" | "template" | "noscript" | "head"
+                )
+            })
+        });
+        if hidden {
+            continue;
+        }
+        let text = node.borrow().to_lowercase();
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric() && c != '+')
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        for (i, word) in words.iter().enumerate() {
+            let (ago, start) = match word.as_str() {
+                "ago" if i >= 2 => {
+                    let Some(unit) = unit_duration(&words[i - 1]) else {
+                        continue;
+                    };
+                    let Some(count) = count(&words[i - 2]) else {
+                        continue;
+                    };
+                    (unit * count, i - 2)
+                }
+                "today" | "yesterday" => {
+                    // Too common in other text without a cue
+                    if cue(&words[..i]).is_none() {
+                        continue;
+                    }
+                    let days = if word == "today" { 0 } else { 1 };
+                    (Duration::days(days), i)
+                }
+                _ => continue,
+            };
+            let Some(date) = anchor.checked_sub_signed(ago) else {
+                continue;
+            };
+            let kind = cue(&words[..start]);
+            let confidence = if kind.is_some() { 0.5 } else { 0.35 };
+            candidates.push(candidate(
+                kind.unwrap_or(PUBLISHED),
+                date,
+                "text".to_string(),
+                confidence,
+            ));
+        }
+    }
+}
+
+/// Most confident candidate of a kind, the first found among equals
+fn best(candidates: &[DateCandidate], kind: &str) -> Option<DateTime<Utc>> {
+    candidates
+        .iter()
+        .filter(|candidate| candidate.kind == kind)
+        .fold(None, |best: Option<&DateCandidate>, candidate| match best {
+            Some(best) if best.confidence >= candidate.confidence => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|candidate| candidate.value)
+}
+
+/// Collect the publication and modification dates of a page and pick the best of each
+///
+/// Dates come from JSON-LD, `article:*_time`, Dublin Core and other meta tags,
+/// `<time datetime>` elements and visible relative dates like "Posted 3 days ago", which
+/// are counted back from `crawled_at` and skipped without it. Each candidate has a
+/// confidence; the most confident one of each kind wins.
+pub fn html_dates(html: &str, crawled_at: Option<DateTime<Utc>>) -> PageDates {
+    let document = parse_document(html, &QueryOptions::default());
+    let mut candidates = Vec::new();
+    for node in jsonld_nodes(html, None) {
+        jsonld_dates(&node, &mut candidates);
+    }
+    meta_dates(html, &mut candidates);
+    time_dates(&document, &mut candidates);
+    // Without an anchor, relative dates would depend on when the query runs
+    if let Some(crawled_at) = crawled_at {
+        text_dates(&document, crawled_at, &mut candidates);
+    }
+
+    PageDates {
+        published: best(&candidates, PUBLISHED),
+        modified: best(&candidates, MODIFIED),
+        candidates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> DateTime<Utc> {
+        parse_datetime(text).unwrap()
+    }
+
+    fn sources(dates: &PageDates) -> Vec<(&str, &str)> {
+        dates
+            .candidates
+            .iter()
+            .map(|candidate| (candidate.kind.as_str(), candidate.source.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_structured_dates() {
+        let dates = html_dates(
+            r#"<head>
+                 <meta property="article:published_time" content="2024-04-30T08:00:00+02:00">
+                 <meta property="article:modified_time" content="2024-05-03T10:00:00Z">
+                 <meta name="DC.date.issued" content="2024-04-29">
+                 <script type="application/ld+json">{"@type": "JobPosting", "datePosted": "2024-05-01"}</script>
+               </head>
+               <body><time datetime="2024-04-01">April</time></body>"#,
+            None,
+        );
+        assert_eq!(dates.published, Some(date("2024-05-01")));
+        assert_eq!(dates.modified, Some(date("2024-05-03T10:00:00Z")));
+        assert_eq!(
+            sources(&dates),
+            vec![
+                ("published", "json-ld:datePosted"),
+                ("published", "meta:article:published_time"),
+                ("published", "meta:dc.date.issued"),
+                ("modified", "meta:article:modified_time"),
+                ("published", "time"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_time_elements() {
+        let dates = html_dates(
+            r#"<time class="entry-date" datetime="2024-03-01">March 1</time>
+               <time itemprop="dateModified" datetime="2024-03-05T12:00:00Z">March 5</time>"#,
+            None,
+        );
+        assert_eq!(dates.published, Some(date("2024-03-01")));
+        assert_eq!(dates.modified, Some(date("2024-03-05T12:00:00Z")));
+    }
+
+    #[test]
+    fn test_relative_text_dates() {
+        let crawled_at = date("2024-05-10T12:00:00Z");
+        let dates = html_dates(
+            r#"<script>var s = "posted 1 day ago";</script>
+               <p>Posted 3 days ago · 120 applicants</p>
+               <p>Updated 2 hours ago</p>"#,
+            Some(crawled_at),
+        );
+        assert_eq!(dates.published, Some(date("2024-05-07T12:00:00Z")));
+        assert_eq!(dates.modified, Some(date("2024-05-10T10:00:00Z")));
+        assert_eq!(dates.candidates.len(), 2);
+
+        let dates = html_dates("<span>30+ days ago</span>", Some(crawled_at));
+        assert_eq!(dates.published, Some(date("2024-04-10T12:00:00Z")));
+        assert_eq!(dates.candidates[0].confidence, 0.35);
+
+        let dates = html_dates("<p>Order today!</p>", Some(crawled_at));
+        assert_eq!(dates, PageDates::default());
+
+        let dates = html_dates("<p>Posted 3 days ago</p>", None);
+        assert_eq!(dates, PageDates::default());
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+/// Formats with a UTC offset that RFC 3339 parsing doesn't accept
+const OFFSET_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M%:z",
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%Y-%m-%dT%H:%M%z",
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+];
+
+/// Formats without a UTC offset, read as UTC
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parse a timestamp as found in feeds, sitemaps and structured data
+///
+/// Accepts RFC 3339 and the W3C profile of ISO 8601 used by sitemaps (down to a bare
+/// year), RFC 2822 dates from RSS, and date-times without an offset, which are read
+/// as UTC.
+pub fn parse_datetime(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(text) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    if let Ok(parsed) = DateTime::parse_from_rfc2822(text) {
+        return Some(parsed.with_timezone(&Utc));
+    }
+    for format in OFFSET_FORMATS {
+        if let Ok(parsed) = DateTime::parse_from_str(text, format) {
+            return Some(parsed.with_timezone(&Utc));
+        }
+    }
+    for format in NAIVE_FORMATS {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(text, format) {
+            return Some(parsed.and_utc());
+        }
+    }
+
+    parse_date(text).map(|date| date.and_time(Default::default()).and_utc())
+}
+
+/// Parse `YYYY-MM-DD`, `YYYY-MM` or `YYYY`, using the first day of the month or year
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let mut parts = text.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next().map(str::parse).unwrap_or(Ok(1)).ok()?;
+    let day = parts.next().map(str::parse).unwrap_or(Ok(1)).ok()?;
+    if !(1000..=9999).contains(&year) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(text: &str) -> Option<String> {
+        parse_datetime(text).map(|dt| dt.to_rfc3339())
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(
+            parsed("2025-01-06T10:00:00+02:00"),
+            Some("2025-01-06T08:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parsed("2025-01-06T10:00:00.5Z"),
+            Some("2025-01-06T10:00:00.500+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_w3c_datetime() {
+        assert_eq!(
+            parsed("2025-01-06T10:00+01:00"),
+            Some("2025-01-06T09:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parsed("2025-01-06"),
+            Some("2025-01-06T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parsed("2025-01"),
+            Some("2025-01-01T00:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parsed("2025"),
+            Some("2025-01-01T00:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rfc2822() {
+        assert_eq!(
+            parsed("Mon, 06 Jan 2025 10:00:00 GMT"),
+            Some("2025-01-06T10:00:00+00:00".to_string())
+        );
+        assert_eq!(
+            parsed("Mon, 06 Jan 2025 10:00:00 +0100"),
+            Some("2025-01-06T09:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_naive_datetime_is_utc() {
+        assert_eq!(
+            parsed("2025-01-06 10:00:00"),
+            Some("2025-01-06T10:00:00+00:00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parsed(""), None);
+        assert_eq!(parsed("yesterday"), None);
+        assert_eq!(parsed("2025-13-01"), None);
+    }
+}
@@ -2,9 +2,31 @@ extern crate duckdb;
 extern crate duckdb_loadable_macros;
 extern crate libduckdb_sys;
 
+mod output;
+
+use crate::ats::{html_ats, AtsEmbed};
+use crate::dates::{html_dates, PageDates};
+use crate::feed::{parse_feed, FeedItem};
+use crate::job_posting::{html_job_posting, JobPosting};
+use crate::json_tree::{html_to_json, json_to_html};
+use crate::jsonld::html_jsonld;
+use crate::label::{html_label_value, html_label_values};
+use crate::list::html_list;
+use crate::meta::{html_meta, PageMeta};
+use crate::microdata::html_microdata;
+use crate::microformats::html_microformats;
+use crate::offer::{html_offers, Offer};
+use crate::rdfa::html_rdfa;
+use crate::salary::{parse_salary, ParsedSalary};
+use crate::sitemap::{parse_sitemap, Sitemap};
+use crate::table::{html_table, html_tables, HeaderMode};
+use crate::tree::{dom_table, TreeNode};
+use crate::validate::{validate_html, ValidationReport};
 use crate::{
-    extract_all_text, extract_all_with_mode, js_decode, process_html, ExtractMode, HqConfig,
+    decode_entities, extract_all_text, extract_all_with_options, js_decode, parse_document,
+    process_html, ExtractMode, HqConfig, QueryOptions,
 };
+use chrono::{DateTime, Utc};
 use duckdb::{
     core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
     ffi,
@@ -18,6 +40,7 @@ use libduckdb_sys::{
     duckdb_data_chunk_get_vector, duckdb_list_entry, duckdb_list_vector_get_child, duckdb_string_t,
     duckdb_vector_get_data,
 };
+use output::{write_values, OutputValue};
 use std::error::Error;
 
 /// Read a list of strings from a list vector at given row index
@@ -55,6 +78,206 @@ unsafe fn read_string_list(
         .collect()
 }
 
+/// Read the raw bytes of a VARCHAR or BLOB column, returning None for NULL rows
+unsafe fn read_bytes(
+    input: &mut DataChunkHandle,
+    col_idx: usize,
+    size: usize,
+) -> Vec<Option<Vec<u8>>> {
+    let vector = input.flat_vector(col_idx);
+    let values = vector.as_slice_with_len::<duckdb_string_t>(size);
+    (0..size)
+        .map(|i| {
+            if vector.row_is_null(i as u64) {
+                None
+            } else {
+                Some(DuckString::new(&mut { values[i] }).as_bytes().to_vec())
+            }
+        })
+        .collect()
+}
+
+/// Read an optional VARCHAR column, returning None for NULL rows or a missing column
+unsafe fn read_optional_strings(
+    input: &mut DataChunkHandle,
+    col_idx: usize,
+    size: usize,
+) -> Vec<Option<String>> {
+    if input.num_columns() <= col_idx {
+        return vec![None; size];
+    }
+
+    let vector = input.flat_vector(col_idx);
+    let values = vector.as_slice_with_len::<duckdb_string_t>(size);
+    (0..size)
+        .map(|i| {
+            if vector.row_is_null(i as u64) {
+                None
+            } else {
+                Some(DuckString::new(&mut { values[i] }).as_str().to_string())
+            }
+        })
+        .collect()
+}
+
+/// Read an optional TIMESTAMP or TIMESTAMPTZ column, returning None for NULL rows or a
+/// missing column
+unsafe fn read_optional_timestamps(
+    input: &mut DataChunkHandle,
+    col_idx: usize,
+    size: usize,
+) -> Vec<Option<DateTime<Utc>>> {
+    if input.num_columns() <= col_idx {
+        return vec![None; size];
+    }
+
+    let vector = input.flat_vector(col_idx);
+    let values = vector.as_slice_with_len::<i64>(size);
+    (0..size)
+        .map(|i| {
+            if vector.row_is_null(i as u64) {
+                None
+            } else {
+                DateTime::from_timestamp_micros(values[i])
+            }
+        })
+        .collect()
+}
+
+/// MAP(VARCHAR, VARCHAR), written as a list of key/value structs
+fn string_map_type() -> LogicalTypeHandle {
+    LogicalTypeHandle::map(
+        &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    )
+}
+
+fn string_map(entries: Vec<(String, String)>) -> OutputValue {
+    OutputValue::list(
+        entries
+            .into_iter()
+            .map(|(key, value)| OutputValue::Struct(vec![key.into(), value.into()])),
+    )
+}
+
+/// Read an optional options column, using the defaults for NULL rows or a missing column
+unsafe fn read_query_options(
+    input: &mut DataChunkHandle,
+    col_idx: usize,
+    size: usize,
+    defaults: &QueryOptions,
+) -> std::result::Result<Vec<QueryOptions>, Box<dyn Error>> {
+    read_optional_strings(input, col_idx, size)
+        .iter()
+        .map(|options| match options {
+            Some(options) => defaults.clone().apply(options),
+            None => Ok(defaults.clone()),
+        })
+        .collect()
+}
+
+/// Read an optional extract column (VARCHAR or VARCHAR[]), using full HTML for NULL rows
+/// or a missing column
+unsafe fn read_extract_modes(
+    input: &mut DataChunkHandle,
+    col_idx: usize,
+    size: usize,
+) -> Vec<ExtractMode> {
+    if input.num_columns() > col_idx {
+        let col_type = input.flat_vector(col_idx).logical_type();
+        let is_list = col_type.id() == LogicalTypeId::List;
+
+        if is_list {
+            // VARCHAR[] - multi-attribute mode
+            let list_entries_vector = input.flat_vector(col_idx);
+            (0..size)
+                .map(|i| {
+                    if list_entries_vector.row_is_null(i as u64) {
+                        ExtractMode::Html
+                    } else {
+                        let attrs = read_string_list(input, col_idx, i, size);
+                        ExtractMode::from_attr_list(&attrs)
+                    }
+                })
+                .collect()
+        } else {
+            // VARCHAR - single attribute mode
+            let extract_vector = input.flat_vector(col_idx);
+            let extract_values = extract_vector.as_slice_with_len::<duckdb_string_t>(size);
+            (0..size)
+                .map(|i| {
+                    if extract_vector.row_is_null(i as u64) {
+                        ExtractMode::Html
+                    } else {
+                        let s = DuckString::new(&mut { extract_values[i] })
+                            .as_str()
+                            .to_string();
+                        ExtractMode::from_attr(Some(&s))
+                    }
+                })
+                .collect()
+        }
+    } else {
+        vec![ExtractMode::Html; size]
+    }
+}
+
+/// Shared implementation of html_query and xml_query
+unsafe fn invoke_html_query(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+    defaults: &QueryOptions,
+) -> std::result::Result<(), Box<dyn Error>> {
+    let size = input.len();
+    let html_vector = input.flat_vector(0);
+    let mut output_vector = output.flat_vector();
+
+    let html_values = html_vector.as_slice_with_len::<duckdb_string_t>(size);
+    let html_contents: Vec<String> = html_values
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
+        .collect();
+
+    // Get selector (optional, column 1)
+    let selectors = read_optional_strings(input, 1, size);
+
+    // Get extract mode (optional, column 2) - can be VARCHAR or VARCHAR[]
+    let extract_modes = read_extract_modes(input, 2, size);
+
+    // Get query options (optional, column 3)
+    let options = read_query_options(input, 3, size, defaults)?;
+
+    for i in 0..size {
+        if html_vector.row_is_null(i as u64) {
+            output_vector.set_null(i);
+            continue;
+        }
+
+        let selector = selectors
+            .get(i)
+            .and_then(|s| s.as_ref())
+            .map(|s| s.as_str())
+            .unwrap_or(":root");
+
+        let mode = &extract_modes[i];
+
+        match extract_all_with_options(&html_contents[i], selector, mode, &options[i]) {
+            Ok(elements) if elements.is_empty() => {
+                output_vector.set_null(i);
+            }
+            Ok(elements) => {
+                // Return first element only
+                output_vector.insert(i, &elements[0]);
+            }
+            Err(_) => {
+                output_vector.set_null(i);
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// HTML query scalar function - returns first matching element
 ///
 /// Extracts first HTML element matching CSS selector.
@@ -67,6 +290,10 @@ unsafe fn read_string_list(
 ///   - '@text' or 'text': inner text content
 ///   - '@href', '@src', etc: attribute value
 ///   - ['@href', '@text']: multiple attributes as JSON object
+/// * `options` - Optional VARCHAR with comma-separated query options:
+///   - 'templates': also match inside `<template>` contents and shadow roots
+///   - 'context=tr': parse the input as a fragment inside a `<tr>` element
+///   - 'parser=xml': parse the input as XML (feeds, sitemaps, SVG, XHTML)
 ///
 /// # Returns
 /// * VARCHAR - First matching element/attribute, or NULL if no match
@@ -78,6 +305,12 @@ unsafe fn read_string_list(
 ///
 /// SELECT html_query(html, 'a', ['@href', '@text']) FROM pages;
 /// -- Returns: {"href": "/path", "text": "Link"}
+///
+/// SELECT html_query(html, 'x-card >>> h2', '@text') FROM pages;
+/// -- Returns: heading inside the shadow root of <x-card>
+///
+/// SELECT html_query(feed, 'item > link', '@text', 'parser=xml') FROM feeds;
+/// -- Returns: link of the first RSS item
 /// ```
 struct HtmlQueryFunction;
 
@@ -89,105 +322,7 @@ impl VScalar for HtmlQueryFunction {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> std::result::Result<(), Box<dyn Error>> {
-        let size = input.len();
-        let html_vector = input.flat_vector(0);
-        let mut output_vector = output.flat_vector();
-
-        let html_values = html_vector.as_slice_with_len::<duckdb_string_t>(size);
-        let html_contents: Vec<String> = html_values
-            .iter()
-            .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
-            .collect();
-
-        // Get selector (optional, column 1)
-        let selectors: Vec<Option<String>> = if input.num_columns() > 1 {
-            let selector_vector = input.flat_vector(1);
-            let selector_values = selector_vector.as_slice_with_len::<duckdb_string_t>(size);
-            (0..size)
-                .map(|i| {
-                    if selector_vector.row_is_null(i as u64) {
-                        None
-                    } else {
-                        Some(
-                            DuckString::new(&mut { selector_values[i] })
-                                .as_str()
-                                .to_string(),
-                        )
-                    }
-                })
-                .collect()
-        } else {
-            vec![None; size]
-        };
-
-        // Get extract mode (optional, column 2) - can be VARCHAR or VARCHAR[]
-        let extract_modes: Vec<ExtractMode> = if input.num_columns() > 2 {
-            let col_type = input.flat_vector(2).logical_type();
-            let is_list = col_type.id() == LogicalTypeId::List;
-
-            if is_list {
-                // VARCHAR[] - multi-attribute mode
-                let list_entries_vector = input.flat_vector(2);
-                (0..size)
-                    .map(|i| {
-                        if list_entries_vector.row_is_null(i as u64) {
-                            ExtractMode::Html
-                        } else {
-                            let attrs = read_string_list(input, 2, i, size);
-                            ExtractMode::from_attr_list(&attrs)
-                        }
-                    })
-                    .collect()
-            } else {
-                // VARCHAR - single attribute mode
-                let extract_vector = input.flat_vector(2);
-                let extract_values = extract_vector.as_slice_with_len::<duckdb_string_t>(size);
-                (0..size)
-                    .map(|i| {
-                        if extract_vector.row_is_null(i as u64) {
-                            ExtractMode::Html
-                        } else {
-                            let s = DuckString::new(&mut { extract_values[i] })
-                                .as_str()
-                                .to_string();
-                            ExtractMode::from_attr(Some(&s))
-                        }
-                    })
-                    .collect()
-            }
-        } else {
-            vec![ExtractMode::Html; size]
-        };
-
-        for i in 0..size {
-            if html_vector.row_is_null(i as u64) {
-                output_vector.set_null(i);
-                continue;
-            }
-
-            let selector = selectors
-                .get(i)
-                .and_then(|s| s.as_ref())
-                .map(|s| s.as_str())
-                .unwrap_or(":root");
-
-            let mode = &extract_modes[i];
-
-            match extract_all_with_mode(&html_contents[i], selector, mode) {
-                Ok(elements) if elements.is_empty() => {
-                    output_vector.set_null(i);
-                }
-                Ok(elements) => {
-                    // Return first element only
-                    output_vector.insert(i, &elements[0]);
-                }
-                Err(_) => {
-                    output_vector.set_null(i);
-                }
-            }
-        }
-
-        Ok(())
+        invoke_html_query(input, output, &QueryOptions::default())
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
@@ -254,10 +389,129 @@ impl VScalar for HtmlQueryFunction {
                 ],
                 LogicalTypeHandle::from(LogicalTypeId::Varchar),
             ),
+            // html_query(html VARCHAR, selector, extract, options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            // html_query(html BLOB, selector, extract, options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Blob),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            // html_query(html VARCHAR, selector, extract[], options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            // html_query(html BLOB, selector, extract[], options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Blob),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
         ]
     }
 }
 
+/// Shared implementation of html_query_all and xml_query_all
+unsafe fn invoke_html_query_all(
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+    defaults: &QueryOptions,
+) -> std::result::Result<(), Box<dyn Error>> {
+    let size = input.len();
+    let html_vector = input.flat_vector(0);
+
+    let html_values = html_vector.as_slice_with_len::<duckdb_string_t>(size);
+    let html_contents: Vec<String> = html_values
+        .iter()
+        .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
+        .collect();
+
+    // Get selector (optional, column 1)
+    let selectors = read_optional_strings(input, 1, size);
+
+    // Get extract mode (optional, column 2) - can be VARCHAR or VARCHAR[]
+    let extract_modes = read_extract_modes(input, 2, size);
+
+    // Get query options (optional, column 3)
+    let options = read_query_options(input, 3, size, defaults)?;
+
+    // Collect all results first to calculate total capacity
+    let mut all_results: Vec<Vec<String>> = Vec::with_capacity(size);
+    let mut total_elements = 0;
+
+    for i in 0..size {
+        if html_vector.row_is_null(i as u64) {
+            all_results.push(Vec::new());
+            continue;
+        }
+
+        let selector = selectors
+            .get(i)
+            .and_then(|s| s.as_ref())
+            .map(|s| s.as_str())
+            .unwrap_or(":root");
+
+        let mode = &extract_modes[i];
+
+        match extract_all_with_options(&html_contents[i], selector, mode, &options[i]) {
+            Ok(elements) => {
+                total_elements += elements.len();
+                all_results.push(elements);
+            }
+            Err(_) => {
+                all_results.push(Vec::new());
+            }
+        }
+    }
+
+    // Now populate the list vector
+    let mut list_vector = output.list_vector();
+    let child_vector = list_vector.child(total_elements);
+
+    let mut current_offset = 0;
+    for (i, elements) in all_results.iter().enumerate() {
+        if html_vector.row_is_null(i as u64) {
+            list_vector.set_null(i);
+            continue;
+        }
+
+        // Insert each element into child vector
+        for (j, element) in elements.iter().enumerate() {
+            child_vector.insert(current_offset + j, element.as_str());
+        }
+
+        // Set the entry offset and length
+        list_vector.set_entry(i, current_offset, elements.len());
+        current_offset += elements.len();
+    }
+
+    list_vector.set_len(total_elements);
+
+    Ok(())
+}
+
 /// HTML query all scalar function - returns VARCHAR[] of all matching elements
 ///
 /// Extracts all HTML elements matching CSS selector as a list.
@@ -270,6 +524,10 @@ impl VScalar for HtmlQueryFunction {
 ///   - '@text' or 'text': inner text content
 ///   - '@href', '@src', etc: attribute value
 ///   - ['@href', '@text']: multiple attributes as JSON objects
+/// * `options` - Optional VARCHAR with comma-separated query options:
+///   - 'templates': also match inside `<template>` contents and shadow roots
+///   - 'context=tr': parse the input as a fragment inside a `<tr>` element
+///   - 'parser=xml': parse the input as XML (feeds, sitemaps, SVG, XHTML)
 ///
 /// # Returns
 /// * VARCHAR[] - Array of all matching elements/attributes
@@ -292,128 +550,7 @@ impl VScalar for HtmlQueryAllFunction {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> std::result::Result<(), Box<dyn Error>> {
-        let size = input.len();
-        let html_vector = input.flat_vector(0);
-
-        let html_values = html_vector.as_slice_with_len::<duckdb_string_t>(size);
-        let html_contents: Vec<String> = html_values
-            .iter()
-            .map(|ptr| DuckString::new(&mut { *ptr }).as_str().to_string())
-            .collect();
-
-        // Get selector (optional, column 1)
-        let selectors: Vec<Option<String>> = if input.num_columns() > 1 {
-            let selector_vector = input.flat_vector(1);
-            let selector_values = selector_vector.as_slice_with_len::<duckdb_string_t>(size);
-            (0..size)
-                .map(|i| {
-                    if selector_vector.row_is_null(i as u64) {
-                        None
-                    } else {
-                        Some(
-                            DuckString::new(&mut { selector_values[i] })
-                                .as_str()
-                                .to_string(),
-                        )
-                    }
-                })
-                .collect()
-        } else {
-            vec![None; size]
-        };
-
-        // Get extract mode (optional, column 2) - can be VARCHAR or VARCHAR[]
-        let extract_modes: Vec<ExtractMode> = if input.num_columns() > 2 {
-            let col_type = input.flat_vector(2).logical_type();
-            let is_list = col_type.id() == LogicalTypeId::List;
-
-            if is_list {
-                // VARCHAR[] - multi-attribute mode
-                let list_entries_vector = input.flat_vector(2);
-                (0..size)
-                    .map(|i| {
-                        if list_entries_vector.row_is_null(i as u64) {
-                            ExtractMode::Html
-                        } else {
-                            let attrs = read_string_list(input, 2, i, size);
-                            ExtractMode::from_attr_list(&attrs)
-                        }
-                    })
-                    .collect()
-            } else {
-                // VARCHAR - single attribute mode
-                let extract_vector = input.flat_vector(2);
-                let extract_values = extract_vector.as_slice_with_len::<duckdb_string_t>(size);
-                (0..size)
-                    .map(|i| {
-                        if extract_vector.row_is_null(i as u64) {
-                            ExtractMode::Html
-                        } else {
-                            let s = DuckString::new(&mut { extract_values[i] })
-                                .as_str()
-                                .to_string();
-                            ExtractMode::from_attr(Some(&s))
-                        }
-                    })
-                    .collect()
-            }
-        } else {
-            vec![ExtractMode::Html; size]
-        };
-
-        // Collect all results first to calculate total capacity
-        let mut all_results: Vec<Vec<String>> = Vec::with_capacity(size);
-        let mut total_elements = 0;
-
-        for i in 0..size {
-            if html_vector.row_is_null(i as u64) {
-                all_results.push(Vec::new());
-                continue;
-            }
-
-            let selector = selectors
-                .get(i)
-                .and_then(|s| s.as_ref())
-                .map(|s| s.as_str())
-                .unwrap_or(":root");
-
-            let mode = &extract_modes[i];
-
-            match extract_all_with_mode(&html_contents[i], selector, mode) {
-                Ok(elements) => {
-                    total_elements += elements.len();
-                    all_results.push(elements);
-                }
-                Err(_) => {
-                    all_results.push(Vec::new());
-                }
-            }
-        }
-
-        // Now populate the list vector
-        let mut list_vector = output.list_vector();
-        let child_vector = list_vector.child(total_elements);
-
-        let mut current_offset = 0;
-        for (i, elements) in all_results.iter().enumerate() {
-            if html_vector.row_is_null(i as u64) {
-                list_vector.set_null(i);
-                continue;
-            }
-
-            // Insert each element into child vector
-            for (j, element) in elements.iter().enumerate() {
-                child_vector.insert(current_offset + j, element.as_str());
-            }
-
-            // Set the entry offset and length
-            list_vector.set_entry(i, current_offset, elements.len());
-            current_offset += elements.len();
-        }
-
-        list_vector.set_len(total_elements);
-
-        Ok(())
+        invoke_html_query_all(input, output, &QueryOptions::default())
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
@@ -480,22 +617,113 @@ impl VScalar for HtmlQueryAllFunction {
                 ],
                 LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
             ),
-        ]
-    }
-}
-
-/// Parse JSON string and decode HTML entities in values
-fn parse_and_decode_json(input: &str) -> Option<String> {
-    let trimmed = input.trim();
-
-    // Try to parse JSON first, then decode HTML entities in string values
+            // html_query_all(html VARCHAR, selector, extract, options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+            // html_query_all(html BLOB, selector, extract, options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Blob),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+            // html_query_all(html VARCHAR, selector, extract[], options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+            // html_query_all(html BLOB, selector, extract[], options)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Blob),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+        ]
+    }
+}
+
+/// XML query scalar function - html_query with the XML parser
+///
+/// Same arguments as `html_query`, but the input is parsed with xml5ever: element names
+/// keep their case, CDATA sections are text and `<link>` is not a void element.
+///
+/// # Examples
+/// ```sql
+/// SELECT xml_query(feed, 'channel > title', '@text') FROM feeds;
+/// ```
+struct XmlQueryFunction;
+
+impl VScalar for XmlQueryFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        invoke_html_query(input, output, &QueryOptions::xml())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        HtmlQueryFunction::signatures()
+    }
+}
+
+/// XML query all scalar function - html_query_all with the XML parser
+///
+/// # Examples
+/// ```sql
+/// SELECT xml_query_all(sitemap, 'url > loc', '@text') FROM sitemaps;
+/// ```
+struct XmlQueryAllFunction;
+
+impl VScalar for XmlQueryAllFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        invoke_html_query_all(input, output, &QueryOptions::xml())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        HtmlQueryAllFunction::signatures()
+    }
+}
+
+/// Parse JSON string and decode HTML entities in values
+fn parse_and_decode_json(input: &str) -> Option<String> {
+    let trimmed = input.trim();
+
+    // Try to parse JSON first, then decode HTML entities in string values
     if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(trimmed) {
         let decoded_json = decode_html_in_json(json_val);
         return serde_json::to_string(&decoded_json).ok();
     }
 
     // If not valid JSON, try decoding entities first then parsing
-    let decoded = htmlescape::decode_html(trimmed).unwrap_or_else(|_| trimmed.to_string());
+    let decoded = decode_entities(trimmed);
     if let Ok(json_val) = serde_json::from_str::<serde_json::Value>(&decoded) {
         return serde_json::to_string(&json_val).ok();
     }
@@ -508,7 +736,7 @@ fn parse_and_decode_json(input: &str) -> Option<String> {
 fn decode_html_in_json(value: serde_json::Value) -> serde_json::Value {
     match value {
         serde_json::Value::String(s) => {
-            let decoded = htmlescape::decode_html(&s).unwrap_or(s);
+            let decoded = decode_entities(&s);
             serde_json::Value::String(decoded)
         }
         serde_json::Value::Array(arr) => {
@@ -725,6 +953,1877 @@ impl VScalar for HtmlExtractJsonFunction {
     }
 }
 
+/// Sitemap entries scalar function - parses sitemap.xml and sitemap index files
+///
+/// Accepts the sitemap as VARCHAR or as a BLOB, which may be gzip-compressed
+/// (`sitemap.xml.gz`). Entries with an invalid `<loc>` are skipped.
+///
+/// # Returns
+/// * LIST(STRUCT) - One struct per `<url>` or `<sitemap>` entry:
+///   loc, lastmod TIMESTAMP, changefreq, priority DOUBLE, is_index BOOLEAN,
+///   images, news, videos and hreflang alternates. NULL if the input is not a sitemap.
+///
+/// # Examples
+/// ```sql
+/// SELECT unnest(sitemap_entries(body), recursive := true) FROM responses;
+///
+/// -- Follow sitemap indexes
+/// SELECT e.loc FROM responses, unnest(sitemap_entries(body)) AS t(e) WHERE e.is_index;
+/// ```
+struct SitemapEntriesFunction;
+
+impl SitemapEntriesFunction {
+    fn image_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("loc", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("title", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("caption", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ])
+    }
+
+    fn news_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("title", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "publication_name",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "publication_language",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "publication_date",
+                LogicalTypeHandle::from(LogicalTypeId::Timestamp),
+            ),
+        ])
+    }
+
+    fn video_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("title", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "description",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "thumbnail_loc",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "content_loc",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "player_loc",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("duration", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+            (
+                "publication_date",
+                LogicalTypeHandle::from(LogicalTypeId::Timestamp),
+            ),
+        ])
+    }
+
+    fn alternate_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("hreflang", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("href", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ])
+    }
+
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+            ("loc", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("lastmod", LogicalTypeHandle::from(LogicalTypeId::Timestamp)),
+            (
+                "changefreq",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("priority", LogicalTypeHandle::from(LogicalTypeId::Double)),
+            ("is_index", LogicalTypeHandle::from(LogicalTypeId::Boolean)),
+            ("images", LogicalTypeHandle::list(&Self::image_type())),
+            ("news", Self::news_type()),
+            ("videos", LogicalTypeHandle::list(&Self::video_type())),
+            (
+                "alternates",
+                LogicalTypeHandle::list(&Self::alternate_type()),
+            ),
+        ]))
+    }
+
+    fn to_output(sitemap: Sitemap) -> OutputValue {
+        let is_index = sitemap.is_index;
+        OutputValue::list(sitemap.entries.into_iter().map(|entry| {
+            OutputValue::Struct(vec![
+                entry.loc.into(),
+                OutputValue::timestamp(entry.lastmod),
+                entry.changefreq.into(),
+                entry.priority.into(),
+                is_index.into(),
+                OutputValue::list(entry.images.into_iter().map(|image| {
+                    OutputValue::Struct(vec![
+                        image.loc.into(),
+                        image.title.into(),
+                        image.caption.into(),
+                    ])
+                })),
+                entry
+                    .news
+                    .map(|news| {
+                        OutputValue::Struct(vec![
+                            news.title.into(),
+                            news.publication_name.into(),
+                            news.publication_language.into(),
+                            OutputValue::timestamp(news.publication_date),
+                        ])
+                    })
+                    .into(),
+                OutputValue::list(entry.videos.into_iter().map(|video| {
+                    OutputValue::Struct(vec![
+                        video.title.into(),
+                        video.description.into(),
+                        video.thumbnail_loc.into(),
+                        video.content_loc.into(),
+                        video.player_loc.into(),
+                        video.duration.into(),
+                        OutputValue::timestamp(video.publication_date),
+                    ])
+                })),
+                OutputValue::list(entry.alternates.into_iter().map(|alternate| {
+                    OutputValue::Struct(vec![alternate.hreflang.into(), alternate.href.into()])
+                })),
+            ])
+        }))
+    }
+}
+
+impl VScalar for SitemapEntriesFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_bytes(input, 0, size)
+            .into_iter()
+            .map(|content| {
+                content
+                    .and_then(|content| parse_sitemap(&content).ok())
+                    .map(Self::to_output)
+                    .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // sitemap_entries(content VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+            // sitemap_entries(content BLOB), optionally gzip-compressed
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Blob)],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// Feed items scalar function - normalizes RSS, Atom and JSON Feed items
+///
+/// Supports RSS 2.0, RSS 1.0 (RDF), Atom and JSON Feed. Relative links are resolved
+/// against the feed's own link, or the optional `base_url`.
+///
+/// # Arguments
+/// * `content` - VARCHAR or BLOB with the feed document
+/// * `base_url` - Optional VARCHAR used when the feed doesn't declare its own link
+///
+/// # Returns
+/// * LIST(STRUCT) - id, title, link, published TIMESTAMP, updated TIMESTAMP, author,
+///   categories VARCHAR[], summary_html, content_html. NULL if the input is not a feed.
+///
+/// # Examples
+/// ```sql
+/// SELECT unnest(feed_items(body), recursive := true) FROM responses;
+///
+/// SELECT html_query_all(item.content_html, 'li', '@text')
+/// FROM responses, unnest(feed_items(body)) AS t(item);
+/// ```
+struct FeedItemsFunction;
+
+impl FeedItemsFunction {
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+            ("id", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("title", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("link", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "published",
+                LogicalTypeHandle::from(LogicalTypeId::Timestamp),
+            ),
+            ("updated", LogicalTypeHandle::from(LogicalTypeId::Timestamp)),
+            ("author", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "categories",
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+            (
+                "summary_html",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            (
+                "content_html",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]))
+    }
+
+    fn to_output(items: Vec<FeedItem>) -> OutputValue {
+        OutputValue::list(items.into_iter().map(|item| {
+            OutputValue::Struct(vec![
+                item.id.into(),
+                item.title.into(),
+                item.link.into(),
+                OutputValue::timestamp(item.published),
+                OutputValue::timestamp(item.updated),
+                item.author.into(),
+                OutputValue::list(item.categories),
+                item.summary_html.into(),
+                item.content_html.into(),
+            ])
+        }))
+    }
+}
+
+impl VScalar for FeedItemsFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let contents = read_bytes(input, 0, size);
+        let base_urls = read_optional_strings(input, 1, size);
+
+        let values: Vec<OutputValue> = contents
+            .into_iter()
+            .zip(base_urls)
+            .map(|(content, base_url)| {
+                content
+                    .and_then(|content| {
+                        parse_feed(&String::from_utf8_lossy(&content), base_url.as_deref()).ok()
+                    })
+                    .map(Self::to_output)
+                    .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // feed_items(content VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+            // feed_items(content BLOB)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Blob)],
+                Self::return_type(),
+            ),
+            // feed_items(content VARCHAR, base_url VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                Self::return_type(),
+            ),
+            // feed_items(content BLOB, base_url VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Blob),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML validate scalar function - parse errors and conformance problems
+///
+/// Reports html5ever's parse errors together with duplicate IDs and elements that were
+/// never closed (ignoring elements with optional end tags like `<p>` and `<li>`).
+///
+/// # Returns
+/// * LIST(STRUCT(line BIGINT, col BIGINT, message VARCHAR)) - ordered by line. `col` is
+///   NULL, as html5ever only tracks line numbers. An empty list means no problems.
+///
+/// # Examples
+/// ```sql
+/// SELECT url, len(html_validate(html)) AS problems FROM pages ORDER BY problems DESC;
+/// ```
+struct HtmlValidateFunction;
+
+impl VScalar for HtmlValidateFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| {
+                html.map(|html| {
+                    OutputValue::list(validate_html(&html).messages.into_iter().map(|message| {
+                        OutputValue::Struct(vec![
+                            (message.line as i64).into(),
+                            message.col.map(|col| col as i64).into(),
+                            message.message.into(),
+                        ])
+                    }))
+                })
+                .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let return_type = || {
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                ("line", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+                ("col", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+                ("message", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ]))
+        };
+        vec![
+            // html_validate(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                return_type(),
+            ),
+            // html_validate(html BLOB)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Blob)],
+                return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML validate summary scalar function - conformance flags of a document
+///
+/// # Returns
+/// * STRUCT - is_valid BOOLEAN, error_count BIGINT, quirks_mode VARCHAR ('no-quirks',
+///   'limited-quirks' or 'quirks'), has_doctype BOOLEAN, doctype VARCHAR,
+///   duplicate_ids VARCHAR[], unclosed_elements VARCHAR[]
+///
+/// # Examples
+/// ```sql
+/// SELECT url FROM pages WHERE html_validate_summary(html).quirks_mode = 'quirks';
+/// ```
+struct HtmlValidateSummaryFunction;
+
+impl HtmlValidateSummaryFunction {
+    fn to_output(report: ValidationReport) -> OutputValue {
+        OutputValue::Struct(vec![
+            report.messages.is_empty().into(),
+            (report.messages.len() as i64).into(),
+            report.quirks_mode_name().into(),
+            report.doctype.is_some().into(),
+            report.doctype.into(),
+            OutputValue::list(report.duplicate_ids),
+            OutputValue::list(report.unclosed_elements),
+        ])
+    }
+}
+
+impl VScalar for HtmlValidateSummaryFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| {
+                html.map(|html| Self::to_output(validate_html(&html)))
+                    .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let return_type = || {
+            LogicalTypeHandle::struct_type(&[
+                ("is_valid", LogicalTypeHandle::from(LogicalTypeId::Boolean)),
+                (
+                    "error_count",
+                    LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                ),
+                (
+                    "quirks_mode",
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ),
+                (
+                    "has_doctype",
+                    LogicalTypeHandle::from(LogicalTypeId::Boolean),
+                ),
+                ("doctype", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                (
+                    "duplicate_ids",
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                ),
+                (
+                    "unclosed_elements",
+                    LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                ),
+            ])
+        };
+        vec![
+            // html_validate_summary(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                return_type(),
+            ),
+            // html_validate_summary(html BLOB)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Blob)],
+                return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML tree scalar function - the DOM as a list of rows
+///
+/// Walks the parsed document in document order, with one row per element, comment and
+/// text node. Doctypes and whitespace-only text are left out.
+///
+/// # Returns
+/// * LIST(STRUCT) - node_id, parent_id, prev_sibling_id, depth, child_index (BIGINTs),
+///   node_type ('element', 'text' or 'comment'), tag, attributes MAP(VARCHAR, VARCHAR)
+///   and own_text. Top-level nodes have a NULL parent_id.
+///
+/// # Examples
+/// ```sql
+/// SELECT unnest(html_tree(html), recursive := true) FROM pages;
+///
+/// -- Most common tags
+/// SELECT n.tag, count(*) FROM pages, unnest(html_tree(html)) AS t(n)
+/// WHERE n.node_type = 'element' GROUP BY ALL ORDER BY 2 DESC;
+/// ```
+struct HtmlTreeFunction;
+
+impl HtmlTreeFunction {
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+            ("node_id", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+            ("parent_id", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+            (
+                "prev_sibling_id",
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+            ("depth", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+            ("node_type", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("tag", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("attributes", string_map_type()),
+            ("own_text", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "child_index",
+                LogicalTypeHandle::from(LogicalTypeId::Bigint),
+            ),
+        ]))
+    }
+
+    fn to_output(rows: Vec<TreeNode>) -> OutputValue {
+        OutputValue::list(rows.into_iter().map(|row| {
+            OutputValue::Struct(vec![
+                row.node_id.into(),
+                row.parent_id.into(),
+                row.prev_sibling_id.into(),
+                row.depth.into(),
+                row.node_type.name().into(),
+                row.tag.into(),
+                string_map(row.attributes),
+                row.own_text.into(),
+                row.child_index.into(),
+            ])
+        }))
+    }
+}
+
+impl VScalar for HtmlTreeFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| {
+                html.map(|html| {
+                    let document = parse_document(&html, &QueryOptions::default());
+                    Self::to_output(dom_table(&document))
+                })
+                .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_tree(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML to JSON scalar function - the DOM as a nested JSON tree
+///
+/// Elements become `{"tag", "attrs", "children"}` objects, text nodes strings and
+/// comments `{"comment"}` objects.
+///
+/// # Signatures
+/// * `html_to_json(html)` - The whole document, from the `<html>` element down
+/// * `html_to_json(html, selector)` - The first element matching the selector
+///
+/// # Returns
+/// * VARCHAR - JSON object, NULL if nothing matches
+///
+/// # Examples
+/// ```sql
+/// SELECT html_to_json(html, '#job')::JSON->'children' FROM pages;
+/// ```
+struct HtmlToJsonFunction;
+
+impl VScalar for HtmlToJsonFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let selectors = read_optional_strings(input, 1, size);
+        let mut output_vector = output.flat_vector();
+
+        for (i, (html, selector)) in html_values.into_iter().zip(selectors).enumerate() {
+            let json = match html {
+                Some(html) => html_to_json(&html, selector.as_deref())?,
+                None => None,
+            };
+            match json {
+                Some(json) => output_vector.insert(i, json.as_str()),
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_to_json(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            // html_to_json(html VARCHAR, selector VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// JSON to HTML scalar function - serialize a JSON tree from `html_to_json` as HTML
+///
+/// Accepts a single node or an array of nodes. Text and attribute values are escaped.
+///
+/// # Returns
+/// * VARCHAR - HTML, NULL if the JSON is invalid or not a tree of nodes
+///
+/// # Examples
+/// ```sql
+/// SELECT json_to_html(html_to_json(html, '#job')) FROM pages;
+/// ```
+struct JsonToHtmlFunction;
+
+impl VScalar for JsonToHtmlFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let mut output_vector = output.flat_vector();
+
+        for (i, json) in read_optional_strings(input, 0, size)
+            .into_iter()
+            .enumerate()
+        {
+            match json.and_then(|json| json_to_html(&json).ok()) {
+                Some(html) => output_vector.insert(i, html.as_str()),
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // json_to_html(json VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML table scalar function - one struct per data row of a table
+///
+/// Columns are named from `<thead>` rows or leading rows of `<th>` cells, cells spanning
+/// several rows or columns are repeated in each of them, and key/value tables (labels in
+/// the first column) are turned into records.
+///
+/// # Arguments
+/// * `html` - HTML document
+/// * `selector` - CSS selector of the table, or of an element containing it
+/// * `header` - Optional 'auto' (default), 'first_row', 'none' or 'vertical'
+///
+/// # Returns
+/// * LIST(STRUCT(row_index BIGINT, cells MAP(VARCHAR, VARCHAR), links MAP(VARCHAR, VARCHAR)))
+///   - cell text and the first link of each cell by column name. NULL if there is no table.
+///
+/// # Examples
+/// ```sql
+/// SELECT r.cells['Title'], r.cells['Salary'], r.links['Title']
+/// FROM pages, unnest(html_table(html, '#jobs')) AS t(r);
+/// ```
+struct HtmlTableFunction;
+
+impl VScalar for HtmlTableFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let selectors = read_optional_strings(input, 1, size);
+        let headers = read_optional_strings(input, 2, size);
+
+        let mut values = Vec::with_capacity(size);
+        for ((html, selector), header) in html_values.into_iter().zip(selectors).zip(headers) {
+            let header = match header {
+                Some(header) => HeaderMode::parse(&header)?,
+                None => HeaderMode::Auto,
+            };
+            let table = match (html, selector) {
+                (Some(html), Some(selector)) => html_table(&html, &selector, header)?,
+                _ => None,
+            };
+            values.push(
+                table
+                    .map(|table| {
+                        OutputValue::list(table.rows.into_iter().enumerate().map(|(idx, row)| {
+                            OutputValue::Struct(vec![
+                                (idx as i64).into(),
+                                string_map(row.cells),
+                                string_map(row.links),
+                            ])
+                        }))
+                    })
+                    .unwrap_or(OutputValue::Null),
+            );
+        }
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        let return_type = || {
+            LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                ("row_index", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+                ("cells", string_map_type()),
+                ("links", string_map_type()),
+            ]))
+        };
+        vec![
+            // html_table(html VARCHAR, selector VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                return_type(),
+            ),
+            // html_table(html VARCHAR, selector VARCHAR, header VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML tables scalar function - overview of every table in a document
+///
+/// # Returns
+/// * LIST(STRUCT) - table_index (0-based, document order), id, caption, row_count,
+///   column_count, columns VARCHAR[], is_vertical and is_nested, with headers detected
+///   as by `html_table(html, selector, 'auto')`
+///
+/// # Examples
+/// ```sql
+/// SELECT t.id, t.columns FROM pages, unnest(html_tables(html)) AS x(t) WHERE t.row_count > 3;
+/// ```
+struct HtmlTablesFunction;
+
+impl VScalar for HtmlTablesFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| {
+                html.map(|html| {
+                    OutputValue::list(html_tables(&html).into_iter().enumerate().map(
+                        |(idx, table)| {
+                            OutputValue::Struct(vec![
+                                (idx as i64).into(),
+                                table.id.into(),
+                                table.caption.into(),
+                                (table.rows.len() as i64).into(),
+                                (table.columns.len() as i64).into(),
+                                OutputValue::list(table.columns),
+                                table.is_vertical.into(),
+                                table.is_nested.into(),
+                            ])
+                        },
+                    ))
+                })
+                .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_tables(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                    (
+                        "table_index",
+                        LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                    ),
+                    ("id", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    ("caption", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    ("row_count", LogicalTypeHandle::from(LogicalTypeId::Bigint)),
+                    (
+                        "column_count",
+                        LogicalTypeHandle::from(LogicalTypeId::Bigint),
+                    ),
+                    (
+                        "columns",
+                        LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    ),
+                    (
+                        "is_vertical",
+                        LogicalTypeHandle::from(LogicalTypeId::Boolean),
+                    ),
+                    ("is_nested", LogicalTypeHandle::from(LogicalTypeId::Boolean)),
+                ])),
+            ),
+        ]
+    }
+}
+
+/// HTML list scalar function - nested `<ul>`/`<ol>` lists as a JSON tree
+///
+/// # Arguments
+/// * `html` - HTML document
+/// * `selector` - CSS selector of the list, or of an element containing it
+///
+/// # Returns
+/// * VARCHAR - JSON array of `{"text", "links", "children"}` items, where `text` is the
+///   item's own text without its nested lists. NULL if there is no list.
+///
+/// # Examples
+/// ```sql
+/// SELECT unnest(html_list(html, '.requirements')::JSON->>'$[*].text') FROM pages;
+/// ```
+struct HtmlListFunction;
+
+impl VScalar for HtmlListFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let selectors = read_optional_strings(input, 1, size);
+        let mut output_vector = output.flat_vector();
+
+        for (i, (html, selector)) in html_values.into_iter().zip(selectors).enumerate() {
+            let json = match (html, selector) {
+                (Some(html), Some(selector)) => html_list(&html, &selector)?,
+                _ => None,
+            };
+            match json {
+                Some(json) => output_vector.insert(i, json.as_str()),
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_list(html VARCHAR, selector VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML label values scalar function - facts shown as label/value pairs
+///
+/// Collects `<dt>`/`<dd>` definitions, `<th>`/`<td>` rows, `<strong>Label:</strong> value`
+/// runs and `Label: value` lines. Labels are normalized to lowercase snake_case
+/// ("Job Type:" becomes `job_type`) and the first value of each label is kept.
+///
+/// # Signatures
+/// * `html_label_values(html)` - Pairs of the whole document
+/// * `html_label_values(html, selector)` - Pairs inside the elements matching the selector
+///
+/// # Returns
+/// * MAP(VARCHAR, VARCHAR)
+///
+/// # Examples
+/// ```sql
+/// SELECT html_label_values(html, '.job-details')['location'] FROM pages;
+/// ```
+struct HtmlLabelValuesFunction;
+
+impl VScalar for HtmlLabelValuesFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let selectors = read_optional_strings(input, 1, size);
+
+        let mut values = Vec::with_capacity(size);
+        for (html, selector) in html_values.into_iter().zip(selectors) {
+            values.push(match html {
+                Some(html) => string_map(html_label_values(&html, selector.as_deref())?),
+                None => OutputValue::Null,
+            });
+        }
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_label_values(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                string_map_type(),
+            ),
+            // html_label_values(html VARCHAR, selector VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                string_map_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML label value scalar function - value of a single label, matched fuzzily
+///
+/// Looks for the exact normalized label first, then for labels containing all of its
+/// words ("Salary" finds "Annual salary"), then for close spellings.
+///
+/// # Returns
+/// * VARCHAR - the value, NULL if no label matches
+///
+/// # Examples
+/// ```sql
+/// SELECT html_label_value(html, 'Salary') FROM pages;
+/// ```
+struct HtmlLabelValueFunction;
+
+impl VScalar for HtmlLabelValueFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let labels = read_optional_strings(input, 1, size);
+        let mut output_vector = output.flat_vector();
+
+        for (i, (html, label)) in html_values.into_iter().zip(labels).enumerate() {
+            let value = match (html, label) {
+                (Some(html), Some(label)) => html_label_value(&html, &label),
+                _ => None,
+            };
+            match value {
+                Some(value) => output_vector.insert(i, value.as_str()),
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_label_value(html VARCHAR, label VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML microdata scalar function - schema.org microdata items as JSON-LD shaped objects
+///
+/// Follows the WHATWG microdata algorithm, including nested `itemscope` and `itemref`.
+/// Schema.org types are compacted under an `https://schema.org` `@context`, so the
+/// results can be queried like `html_extract_json` output of JSON-LD scripts.
+///
+/// # Returns
+/// * VARCHAR - JSON array of the top-level items, `[]` if there are none
+///
+/// # Examples
+/// ```sql
+/// SELECT html_microdata(html)->0->>'@type' FROM pages;
+/// ```
+struct HtmlMicrodataFunction;
+
+impl VScalar for HtmlMicrodataFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let mut output_vector = output.flat_vector();
+
+        for (i, html) in read_optional_strings(input, 0, size)
+            .into_iter()
+            .enumerate()
+        {
+            match html {
+                Some(html) => output_vector.insert(i, html_microdata(&html).as_str()),
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_microdata(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML RDFa scalar function - RDFa Lite items as JSON-LD shaped objects
+///
+/// Reads `vocab`, `typeof`, `property`, `resource` and `prefix`. Terms are expanded to IRIs,
+/// with schema.org ones compacted under an `https://schema.org` `@context`, and IRIs are
+/// resolved against the page's `<base>`.
+///
+/// # Returns
+/// * VARCHAR - JSON array of the top-level items, `[]` if there are none
+///
+/// # Examples
+/// ```sql
+/// SELECT html_rdfa(html)->0->>'title' FROM pages;
+/// ```
+struct HtmlRdfaFunction;
+
+impl VScalar for HtmlRdfaFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let mut output_vector = output.flat_vector();
+
+        for (i, html) in read_optional_strings(input, 0, size)
+            .into_iter()
+            .enumerate()
+        {
+            match html {
+                Some(html) => output_vector.insert(i, html_rdfa(&html).as_str()),
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_rdfa(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML microformats scalar function - microformats2 items, rels and rel-urls as JSON
+///
+/// Implements the mf2 parsing rules for `h-*` roots and `p-`, `u-`, `dt-` and `e-`
+/// properties, including implied `name`, `photo` and `url` properties.
+///
+/// # Signatures
+/// * `html_microformats(html)` - URLs resolved against the document's `<base>` only
+/// * `html_microformats(html, base_url)` - URLs resolved against `<base>` or `base_url`
+///
+/// # Returns
+/// * VARCHAR - `{"items": [...], "rels": {...}, "rel-urls": {...}}`
+///
+/// # Examples
+/// ```sql
+/// SELECT html_microformats(html, url)->'items'->0->'properties'->'name'->>0 FROM pages;
+/// ```
+struct HtmlMicroformatsFunction;
+
+impl VScalar for HtmlMicroformatsFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let base_urls = read_optional_strings(input, 1, size);
+        let mut output_vector = output.flat_vector();
+
+        for (i, (html, base_url)) in html_values.into_iter().zip(base_urls).enumerate() {
+            match html {
+                Some(html) => {
+                    output_vector.insert(i, html_microformats(&html, base_url.as_deref()).as_str())
+                }
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_microformats(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            // html_microformats(html VARCHAR, base_url VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML meta scalar function - `<meta>` tags and the common page fields
+///
+/// Collects every `<meta>` with a `name`, `property`, `itemprop` or `http-equiv`, keyed by
+/// the lowercased attribute value, so `og:image` and other repeated tags keep all their
+/// values. The typed fields fall back between the usual sources, e.g. `<title>`, then
+/// `og:title`, then `twitter:title`.
+///
+/// # Returns
+/// * STRUCT(tags MAP(VARCHAR, VARCHAR[]), title, description, image, site_name, favicon,
+///   manifest, theme_color) - URLs resolved against `<base>`
+///
+/// # Examples
+/// ```sql
+/// SELECT m.title, m.tags['og:type'][1] FROM (SELECT html_meta(html) AS m FROM pages);
+/// ```
+struct HtmlMetaFunction;
+
+impl HtmlMetaFunction {
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            (
+                "tags",
+                LogicalTypeHandle::map(
+                    &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    &LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                ),
+            ),
+            ("title", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "description",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("image", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("site_name", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("favicon", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("manifest", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "theme_color",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ])
+    }
+
+    fn to_output(meta: PageMeta) -> OutputValue {
+        OutputValue::Struct(vec![
+            OutputValue::list(meta.tags.into_iter().map(|(key, values)| {
+                OutputValue::Struct(vec![key.into(), OutputValue::list(values)])
+            })),
+            meta.title.into(),
+            meta.description.into(),
+            meta.image.into(),
+            meta.site_name.into(),
+            meta.favicon.into(),
+            meta.manifest.into(),
+            meta.theme_color.into(),
+        ])
+    }
+}
+
+impl VScalar for HtmlMetaFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| match html {
+                Some(html) => Self::to_output(html_meta(&html)),
+                None => OutputValue::Null,
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_meta(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML JSON-LD scalar function - normalized JSON-LD nodes, optionally of one type
+///
+/// Reads every `application/ld+json` script, flattens `@graph`s and arrays, inlines nodes
+/// referenced by `@id` and keeps identical nodes once. With a type, returns the nodes whose
+/// `@type` string or array includes it, nested ones included.
+///
+/// # Signatures
+/// * `html_jsonld(html)` - All top-level nodes
+/// * `html_jsonld(html, type)` - Nodes of a type, e.g. 'JobPosting' or 'schema:JobPosting'
+///
+/// # Returns
+/// * VARCHAR - JSON array of nodes, `[]` if there are none
+///
+/// # Examples
+/// ```sql
+/// SELECT html_jsonld(html, 'JobPosting')->0->'hiringOrganization'->>'name' FROM pages;
+/// ```
+struct HtmlJsonldFunction;
+
+impl VScalar for HtmlJsonldFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let node_types = read_optional_strings(input, 1, size);
+        let mut output_vector = output.flat_vector();
+
+        for (i, (html, node_type)) in html_values.into_iter().zip(node_types).enumerate() {
+            match html {
+                Some(html) => {
+                    output_vector.insert(i, html_jsonld(&html, node_type.as_deref()).as_str())
+                }
+                None => output_vector.set_null(i),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_jsonld(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            // html_jsonld(html VARCHAR, type VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ]
+    }
+}
+
+/// HTML job posting scalar function - the page's schema.org JobPosting as a typed struct
+///
+/// Looks for a JobPosting in the JSON-LD scripts, then in microdata, then in RDFa, and
+/// normalizes the fields every pipeline needs.
+///
+/// # Returns
+/// * STRUCT(source, title, hiring_organization STRUCT(name, url, logo), date_posted TIMESTAMP,
+///   valid_through TIMESTAMP, employment_type VARCHAR[], job_locations STRUCT(...)[],
+///   applicant_location_requirements VARCHAR[], job_location_type, is_remote BOOLEAN,
+///   base_salary STRUCT(min DOUBLE, max DOUBLE, currency, unit), direct_apply BOOLEAN,
+///   description_html) - NULL if the page has no JobPosting
+///
+/// # Examples
+/// ```sql
+/// SELECT j.title, j.hiring_organization.name, j.base_salary.min
+/// FROM (SELECT html_job_posting(html) AS j FROM pages);
+/// ```
+struct HtmlJobPostingFunction;
+
+impl HtmlJobPostingFunction {
+    fn organization_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("name", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("url", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("logo", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ])
+    }
+
+    fn location_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("name", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "street_address",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("locality", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("region", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "postal_code",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("country", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ])
+    }
+
+    fn salary_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("min", LogicalTypeHandle::from(LogicalTypeId::Double)),
+            ("max", LogicalTypeHandle::from(LogicalTypeId::Double)),
+            ("currency", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("unit", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ])
+    }
+
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("source", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("title", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("hiring_organization", Self::organization_type()),
+            (
+                "date_posted",
+                LogicalTypeHandle::from(LogicalTypeId::Timestamp),
+            ),
+            (
+                "valid_through",
+                LogicalTypeHandle::from(LogicalTypeId::Timestamp),
+            ),
+            (
+                "employment_type",
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+            (
+                "job_locations",
+                LogicalTypeHandle::list(&Self::location_type()),
+            ),
+            (
+                "applicant_location_requirements",
+                LogicalTypeHandle::list(&LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ),
+            (
+                "job_location_type",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("is_remote", LogicalTypeHandle::from(LogicalTypeId::Boolean)),
+            ("base_salary", Self::salary_type()),
+            (
+                "direct_apply",
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+            (
+                "description_html",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+        ])
+    }
+
+    fn to_output(posting: JobPosting) -> OutputValue {
+        OutputValue::Struct(vec![
+            posting.source.into(),
+            posting.title.into(),
+            OutputValue::Struct(vec![
+                posting.organization_name.into(),
+                posting.organization_url.into(),
+                posting.organization_logo.into(),
+            ]),
+            OutputValue::timestamp(posting.date_posted),
+            OutputValue::timestamp(posting.valid_through),
+            OutputValue::list(posting.employment_type),
+            OutputValue::list(posting.locations.into_iter().map(|location| {
+                OutputValue::Struct(vec![
+                    location.name.into(),
+                    location.street_address.into(),
+                    location.locality.into(),
+                    location.region.into(),
+                    location.postal_code.into(),
+                    location.country.into(),
+                ])
+            })),
+            OutputValue::list(posting.applicant_location_requirements),
+            posting.job_location_type.into(),
+            posting.is_remote.into(),
+            posting
+                .salary
+                .map(|salary| {
+                    OutputValue::Struct(vec![
+                        salary.min.into(),
+                        salary.max.into(),
+                        salary.currency.into(),
+                        salary.unit.into(),
+                    ])
+                })
+                .into(),
+            posting.direct_apply.into(),
+            posting.description_html.into(),
+        ])
+    }
+}
+
+impl VScalar for HtmlJobPostingFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| {
+                html.and_then(|html| html_job_posting(&html))
+                    .map(Self::to_output)
+                    .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_job_posting(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// Parse salary scalar function - salary text as a structured range
+///
+/// Handles `k`/`m` suffixes, locale separators, currency symbols and ISO codes, and period
+/// keywords in English, German, French, Spanish and the Nordic languages.
+///
+/// # Arguments
+/// * `text` - Salary text such as "€55k–70k per year" or "bis zu 80.000 € brutto"
+/// * `default_currency` - Optional ISO code used when the text names no currency
+///
+/// # Returns
+/// * STRUCT(min DOUBLE, max DOUBLE, currency, period, is_estimate BOOLEAN) - NULL if the
+///   text has no amount
+///
+/// # Examples
+/// ```sql
+/// SELECT parse_salary(html_query(html, '.salary', '@text'), 'EUR').max FROM pages;
+/// ```
+struct ParseSalaryFunction;
+
+impl ParseSalaryFunction {
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("min", LogicalTypeHandle::from(LogicalTypeId::Double)),
+            ("max", LogicalTypeHandle::from(LogicalTypeId::Double)),
+            ("currency", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("period", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "is_estimate",
+                LogicalTypeHandle::from(LogicalTypeId::Boolean),
+            ),
+        ])
+    }
+
+    fn to_output(salary: ParsedSalary) -> OutputValue {
+        OutputValue::Struct(vec![
+            salary.min.into(),
+            salary.max.into(),
+            salary.currency.into(),
+            salary.period.into(),
+            salary.is_estimate.into(),
+        ])
+    }
+}
+
+impl VScalar for ParseSalaryFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let texts = read_optional_strings(input, 0, size);
+        let currencies = read_optional_strings(input, 1, size);
+        let values: Vec<OutputValue> = texts
+            .into_iter()
+            .zip(currencies)
+            .map(|(text, currency)| {
+                text.and_then(|text| parse_salary(&text, currency.as_deref()))
+                    .map(Self::to_output)
+                    .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // parse_salary(text VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+            // parse_salary(text VARCHAR, default_currency VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML ATS scalar function - the applicant tracking system embedded in a career page
+///
+/// Recognizes Greenhouse, Lever, Workday, Ashby, SmartRecruiters, Personio, Teamtailor and
+/// Recruitee from script and iframe URLs, embed elements and bootstrapped data.
+///
+/// # Returns
+/// * STRUCT(provider, board, url, evidence, payload) - `payload` is the bootstrapped job data
+///   as JSON; NULL if no ATS is found
+///
+/// # Examples
+/// ```sql
+/// SELECT a.provider, a.board FROM (SELECT html_ats(html) AS a FROM pages);
+/// ```
+struct HtmlAtsFunction;
+
+impl HtmlAtsFunction {
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("provider", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("board", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("url", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("evidence", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("payload", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+        ])
+    }
+
+    fn to_output(embed: AtsEmbed) -> OutputValue {
+        OutputValue::Struct(vec![
+            embed.provider.into(),
+            embed.board.into(),
+            embed.url.into(),
+            embed.evidence.into(),
+            embed.payload.map(|payload| payload.to_string()).into(),
+        ])
+    }
+}
+
+impl VScalar for HtmlAtsFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| {
+                html.and_then(|html| html_ats(&html))
+                    .map(Self::to_output)
+                    .unwrap_or(OutputValue::Null)
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_ats(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
+/// HTML offers scalar function - product offers with the source of each field
+///
+/// Merges Offer and AggregateOffer data from JSON-LD and microdata with OpenGraph
+/// `product:price:*` tags and `itemprop="price"` markup.
+///
+/// # Returns
+/// * LIST(STRUCT(price DECIMAL(18,4), low_price DECIMAL(18,4), high_price DECIMAL(18,4),
+///   currency, availability, sku, gtin, seller, sources MAP(VARCHAR, VARCHAR)))
+///
+/// # Examples
+/// ```sql
+/// SELECT o.price, o.currency, o.sources['price'] FROM (SELECT unnest(html_offers(html)) AS o FROM pages);
+/// ```
+struct HtmlOffersFunction;
+
+impl HtmlOffersFunction {
+    /// Decimal places of prices
+    const SCALE: u8 = 4;
+
+    fn price_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::decimal(18, Self::SCALE)
+    }
+
+    fn offer_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            ("price", Self::price_type()),
+            ("low_price", Self::price_type()),
+            ("high_price", Self::price_type()),
+            ("currency", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "availability",
+                LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            ),
+            ("sku", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("gtin", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            ("seller", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+            (
+                "sources",
+                LogicalTypeHandle::map(
+                    &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    &LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                ),
+            ),
+        ])
+    }
+
+    fn to_output(offer: Offer) -> OutputValue {
+        let scale = i32::from(Self::SCALE);
+        OutputValue::Struct(vec![
+            OutputValue::decimal(offer.price, scale),
+            OutputValue::decimal(offer.low_price, scale),
+            OutputValue::decimal(offer.high_price, scale),
+            offer.currency.into(),
+            offer.availability.into(),
+            offer.sku.into(),
+            offer.gtin.into(),
+            offer.seller.into(),
+            OutputValue::list(
+                offer
+                    .sources
+                    .into_iter()
+                    .map(|(field, source)| OutputValue::Struct(vec![field.into(), source.into()])),
+            ),
+        ])
+    }
+}
+
+impl VScalar for HtmlOffersFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let values: Vec<OutputValue> = read_optional_strings(input, 0, size)
+            .into_iter()
+            .map(|html| match html {
+                Some(html) => {
+                    OutputValue::list(html_offers(&html).into_iter().map(Self::to_output))
+                }
+                None => OutputValue::Null,
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_offers(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                LogicalTypeHandle::list(&Self::offer_type()),
+            ),
+        ]
+    }
+}
+
+/// HTML dates scalar function - publication and modification dates of a page
+///
+/// Collects candidates from JSON-LD, meta tags, `<time datetime>` elements and relative
+/// dates like "Posted 3 days ago" in the visible text, each with a confidence.
+///
+/// # Arguments
+/// * `html` - HTML document
+/// * `crawled_at` - Optional time the page was fetched, relative dates count back from it
+///   (now without it)
+///
+/// # Returns
+/// * STRUCT(published TIMESTAMPTZ, modified TIMESTAMPTZ, candidates STRUCT(kind,
+///   value TIMESTAMPTZ, source, confidence DOUBLE)[])
+///
+/// # Examples
+/// ```sql
+/// SELECT html_dates(html, fetched_at).published FROM pages;
+/// ```
+struct HtmlDatesFunction;
+
+impl HtmlDatesFunction {
+    fn return_type() -> LogicalTypeHandle {
+        LogicalTypeHandle::struct_type(&[
+            (
+                "published",
+                LogicalTypeHandle::from(LogicalTypeId::TimestampTZ),
+            ),
+            (
+                "modified",
+                LogicalTypeHandle::from(LogicalTypeId::TimestampTZ),
+            ),
+            (
+                "candidates",
+                LogicalTypeHandle::list(&LogicalTypeHandle::struct_type(&[
+                    ("kind", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    ("value", LogicalTypeHandle::from(LogicalTypeId::TimestampTZ)),
+                    ("source", LogicalTypeHandle::from(LogicalTypeId::Varchar)),
+                    ("confidence", LogicalTypeHandle::from(LogicalTypeId::Double)),
+                ])),
+            ),
+        ])
+    }
+
+    fn to_output(dates: PageDates) -> OutputValue {
+        OutputValue::Struct(vec![
+            OutputValue::timestamp(dates.published),
+            OutputValue::timestamp(dates.modified),
+            OutputValue::list(dates.candidates.into_iter().map(|candidate| {
+                OutputValue::Struct(vec![
+                    candidate.kind.into(),
+                    OutputValue::timestamp(Some(candidate.value)),
+                    candidate.source.into(),
+                    candidate.confidence.into(),
+                ])
+            })),
+        ])
+    }
+}
+
+impl VScalar for HtmlDatesFunction {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> std::result::Result<(), Box<dyn Error>> {
+        let size = input.len();
+        let html_values = read_optional_strings(input, 0, size);
+        let crawled_at = read_optional_timestamps(input, 1, size);
+        let values: Vec<OutputValue> = html_values
+            .into_iter()
+            .zip(crawled_at)
+            .map(|(html, crawled_at)| match html {
+                Some(html) => Self::to_output(html_dates(&html, crawled_at)),
+                None => OutputValue::Null,
+            })
+            .collect();
+
+        write_values(output, &values);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            // html_dates(html VARCHAR)
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+                Self::return_type(),
+            ),
+            // html_dates(html VARCHAR, crawled_at TIMESTAMPTZ)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    LogicalTypeHandle::from(LogicalTypeId::TimestampTZ),
+                ],
+                Self::return_type(),
+            ),
+        ]
+    }
+}
+
 /// # Safety
 /// Called by DuckDB to initialize the extension. Must only be called once.
 #[duckdb_entrypoint_c_api()]
@@ -732,5 +2831,29 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_scalar_function::<HtmlQueryFunction>("html_query")?;
     con.register_scalar_function::<HtmlQueryAllFunction>("html_query_all")?;
     con.register_scalar_function::<HtmlExtractJsonFunction>("html_extract_json")?;
+    con.register_scalar_function::<XmlQueryFunction>("xml_query")?;
+    con.register_scalar_function::<XmlQueryAllFunction>("xml_query_all")?;
+    con.register_scalar_function::<SitemapEntriesFunction>("sitemap_entries")?;
+    con.register_scalar_function::<FeedItemsFunction>("feed_items")?;
+    con.register_scalar_function::<HtmlValidateFunction>("html_validate")?;
+    con.register_scalar_function::<HtmlValidateSummaryFunction>("html_validate_summary")?;
+    con.register_scalar_function::<HtmlTreeFunction>("html_tree")?;
+    con.register_scalar_function::<HtmlToJsonFunction>("html_to_json")?;
+    con.register_scalar_function::<JsonToHtmlFunction>("json_to_html")?;
+    con.register_scalar_function::<HtmlTableFunction>("html_table")?;
+    con.register_scalar_function::<HtmlTablesFunction>("html_tables")?;
+    con.register_scalar_function::<HtmlListFunction>("html_list")?;
+    con.register_scalar_function::<HtmlLabelValuesFunction>("html_label_values")?;
+    con.register_scalar_function::<HtmlLabelValueFunction>("html_label_value")?;
+    con.register_scalar_function::<HtmlMicrodataFunction>("html_microdata")?;
+    con.register_scalar_function::<HtmlRdfaFunction>("html_rdfa")?;
+    con.register_scalar_function::<HtmlMicroformatsFunction>("html_microformats")?;
+    con.register_scalar_function::<HtmlMetaFunction>("html_meta")?;
+    con.register_scalar_function::<HtmlJsonldFunction>("html_jsonld")?;
+    con.register_scalar_function::<HtmlJobPostingFunction>("html_job_posting")?;
+    con.register_scalar_function::<ParseSalaryFunction>("parse_salary")?;
+    con.register_scalar_function::<HtmlAtsFunction>("html_ats")?;
+    con.register_scalar_function::<HtmlOffersFunction>("html_offers")?;
+    con.register_scalar_function::<HtmlDatesFunction>("html_dates")?;
     Ok(())
 }
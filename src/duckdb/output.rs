@@ -0,0 +1,190 @@
+use chrono::{DateTime, Utc};
+use duckdb::core::{FlatVector, Inserter, ListVector, StructVector};
+use duckdb::vtab::arrow::WritableVector;
+
+/// A value to write into a (possibly nested) DuckDB output vector
+///
+/// Functions returning STRUCTs and LISTs build one value per row and write them all
+/// with [`write_values`], which takes care of list offsets and child vectors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputValue {
+    Null,
+    Varchar(String),
+    Double(f64),
+    Boolean(bool),
+    BigInt(i64),
+    /// Microseconds since the epoch, for TIMESTAMP and TIMESTAMPTZ
+    Timestamp(i64),
+    List(Vec<OutputValue>),
+    /// Field values in the order of the STRUCT type
+    Struct(Vec<OutputValue>),
+}
+
+const NULL: OutputValue = OutputValue::Null;
+
+impl OutputValue {
+    pub fn timestamp(value: Option<DateTime<Utc>>) -> Self {
+        value
+            .map(|dt| Self::Timestamp(dt.timestamp_micros()))
+            .unwrap_or(Self::Null)
+    }
+
+    /// A DECIMAL of at most 18 digits with `scale` decimals, stored as a scaled BIGINT.
+    /// Values that are not finite or don't fit in 18 digits are NULL rather than clamped.
+    pub fn decimal(value: Option<f64>, scale: i32) -> Self {
+        value
+            .filter(|value| value.is_finite() && value.abs() < 10f64.powi(18 - scale))
+            .map(|value| Self::BigInt((value * 10f64.powi(scale)).round() as i64))
+            .unwrap_or(Self::Null)
+    }
+
+    pub fn list<T: Into<OutputValue>>(values: impl IntoIterator<Item = T>) -> Self {
+        Self::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<String> for OutputValue {
+    fn from(value: String) -> Self {
+        Self::Varchar(value)
+    }
+}
+
+impl From<&str> for OutputValue {
+    fn from(value: &str) -> Self {
+        Self::Varchar(value.to_string())
+    }
+}
+
+impl From<f64> for OutputValue {
+    fn from(value: f64) -> Self {
+        Self::Double(value)
+    }
+}
+
+impl From<bool> for OutputValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<i64> for OutputValue {
+    fn from(value: i64) -> Self {
+        Self::BigInt(value)
+    }
+}
+
+impl<T: Into<OutputValue>> From<Option<T>> for OutputValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+/// How a column has to be written, taken from its first non-NULL value
+enum Shape {
+    Flat,
+    List,
+    Struct,
+}
+
+fn shape(values: &[&OutputValue]) -> Shape {
+    match values.iter().find(|value| ***value != OutputValue::Null) {
+        Some(OutputValue::List(_)) => Shape::List,
+        Some(OutputValue::Struct(_)) => Shape::Struct,
+        _ => Shape::Flat,
+    }
+}
+
+enum Target {
+    Flat(FlatVector),
+    List(ListVector),
+    Struct(StructVector),
+}
+
+/// Write one value per row into the output vector
+pub fn write_values(output: &mut dyn WritableVector, values: &[OutputValue]) {
+    let values: Vec<&OutputValue> = values.iter().collect();
+    let target = match shape(&values) {
+        Shape::Flat => Target::Flat(output.flat_vector()),
+        Shape::List => Target::List(output.list_vector()),
+        Shape::Struct => Target::Struct(output.struct_vector()),
+    };
+    write_target(target, &values);
+}
+
+fn write_target(target: Target, values: &[&OutputValue]) {
+    match target {
+        Target::Flat(mut vector) => {
+            for (row, value) in values.iter().enumerate() {
+                match value {
+                    OutputValue::Varchar(text) => vector.insert(row, text.as_str()),
+                    OutputValue::Double(number) => vector.as_mut_slice::<f64>()[row] = *number,
+                    OutputValue::Boolean(flag) => vector.as_mut_slice::<bool>()[row] = *flag,
+                    OutputValue::BigInt(number) | OutputValue::Timestamp(number) => {
+                        vector.as_mut_slice::<i64>()[row] = *number
+                    }
+                    _ => vector.set_null(row),
+                }
+            }
+        }
+        Target::List(mut vector) => {
+            let mut children: Vec<&OutputValue> = Vec::new();
+            for (row, value) in values.iter().enumerate() {
+                match value {
+                    OutputValue::List(items) => {
+                        vector.set_entry(row, children.len(), items.len());
+                        children.extend(items.iter());
+                    }
+                    _ => {
+                        vector.set_entry(row, children.len(), 0);
+                        vector.set_null(row);
+                    }
+                }
+            }
+
+            let total = children.len();
+            let child = match shape(&children) {
+                Shape::Flat => Target::Flat(vector.child(total)),
+                Shape::List => {
+                    // Reserves the child's capacity before taking it as a list
+                    vector.child(total);
+                    Target::List(vector.list_child())
+                }
+                Shape::Struct => Target::Struct(vector.struct_child(total)),
+            };
+            write_target(child, &children);
+            vector.set_len(total);
+        }
+        Target::Struct(mut vector) => {
+            let width = values
+                .iter()
+                .map(|value| match value {
+                    OutputValue::Struct(fields) => fields.len(),
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0);
+
+            for idx in 0..width {
+                let fields: Vec<&OutputValue> = values
+                    .iter()
+                    .map(|value| match value {
+                        OutputValue::Struct(fields) => fields.get(idx).unwrap_or(&NULL),
+                        _ => &NULL,
+                    })
+                    .collect();
+                let child = match shape(&fields) {
+                    Shape::Flat => Target::Flat(vector.child(idx, values.len())),
+                    Shape::List => Target::List(vector.list_vector_child(idx)),
+                    Shape::Struct => Target::Struct(vector.struct_vector_child(idx)),
+                };
+                write_target(child, &fields);
+            }
+
+            for (row, value) in values.iter().enumerate() {
+                if !matches!(value, OutputValue::Struct(_)) {
+                    vector.set_null(row);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,397 @@
+use crate::datetime::parse_datetime;
+use crate::link::{absolutize_links, resolve_url};
+use crate::xml::{child_elements, child_text, parse_xml};
+use crate::{attribute, name_of};
+use chrono::{DateTime, Utc};
+use kuchikiki::NodeRef;
+use serde_json::Value;
+use std::error::Error;
+use url::Url;
+
+/// An item of an RSS, Atom or JSON Feed, normalized to one schema
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FeedItem {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub updated: Option<DateTime<Utc>>,
+    pub author: Option<String>,
+    pub categories: Vec<String>,
+    pub summary_html: Option<String>,
+    pub content_html: Option<String>,
+}
+
+/// Parse the items of an RSS 2.0, RSS 1.0 (RDF), Atom or JSON Feed document
+///
+/// Relative item links, and links inside the item HTML, are resolved against the feed's
+/// own link (or `xml:base`), falling back to `base_url`.
+pub fn parse_feed(content: &str, base_url: Option<&str>) -> Result<Vec<FeedItem>, Box<dyn Error>> {
+    let content = content.trim_start_matches('\u{feff}').trim();
+    let base = base_url.and_then(|base| Url::parse(base).ok());
+
+    if content.starts_with('{') {
+        return parse_json_feed(content, base);
+    }
+
+    let document = parse_xml(content);
+    let root = child_elements(&document)
+        .next()
+        .ok_or("Feed is not an XML document")?;
+    match name_of(&root).as_str() {
+        "rss" => {
+            let channel = child_elements(&root)
+                .find(|node| name_of(node) == "channel")
+                .ok_or("RSS feed has no <channel>")?;
+            Ok(parse_rss_items(&channel, &channel, base))
+        }
+        // RSS 1.0 keeps items next to the channel instead of inside it
+        "RDF" => {
+            let channel = child_elements(&root).find(|node| name_of(node) == "channel");
+            Ok(parse_rss_items(
+                &root,
+                channel.as_ref().unwrap_or(&root),
+                base,
+            ))
+        }
+        "feed" => Ok(parse_atom(&root, base)),
+        name => Err(format!("Expected <rss>, <rdf:RDF> or <feed>, found <{}>", name).into()),
+    }
+}
+
+fn parse_rss_items(parent: &NodeRef, channel: &NodeRef, base: Option<Url>) -> Vec<FeedItem> {
+    let base = child_text(channel, "link")
+        .and_then(|link| Url::parse(&resolve_url(base.as_ref(), &link)).ok())
+        .or(base);
+
+    child_elements(parent)
+        .filter(|node| name_of(node) == "item")
+        .map(|item| {
+            let link = child_text(&item, "link").map(|link| resolve_url(base.as_ref(), &link));
+            let id = child_text(&item, "guid")
+                .or_else(|| attribute(&item, "about"))
+                .or_else(|| link.clone());
+            FeedItem {
+                id,
+                title: child_text(&item, "title"),
+                link,
+                published: child_text(&item, "pubDate")
+                    .or_else(|| child_text(&item, "date"))
+                    .and_then(|text| parse_datetime(&text)),
+                updated: child_text(&item, "modified").and_then(|text| parse_datetime(&text)),
+                author: child_text(&item, "author").or_else(|| child_text(&item, "creator")),
+                categories: child_texts(&item, &["category", "subject"]),
+                summary_html: child_text(&item, "description")
+                    .map(|html| absolutize(&html, base.as_ref())),
+                content_html: child_text(&item, "encoded")
+                    .map(|html| absolutize(&html, base.as_ref())),
+            }
+        })
+        .collect()
+}
+
+fn parse_atom(feed: &NodeRef, base: Option<Url>) -> Vec<FeedItem> {
+    let base = xml_base(feed, base.as_ref())
+        .or_else(|| {
+            atom_link(feed).and_then(|link| Url::parse(&resolve_url(base.as_ref(), &link)).ok())
+        })
+        .or(base);
+    let feed_author = atom_author(feed);
+
+    child_elements(feed)
+        .filter(|node| name_of(node) == "entry")
+        .map(|entry| {
+            let base = xml_base(&entry, base.as_ref()).or_else(|| base.clone());
+            FeedItem {
+                id: child_text(&entry, "id"),
+                title: atom_text(&entry, "title", base.as_ref()).map(|title| {
+                    crate::parse_fragment(&title, "body", &Default::default())
+                        .text_contents()
+                        .trim()
+                        .to_string()
+                }),
+                link: atom_link(&entry).map(|link| resolve_url(base.as_ref(), &link)),
+                published: child_text(&entry, "published")
+                    .or_else(|| child_text(&entry, "issued"))
+                    .and_then(|text| parse_datetime(&text)),
+                updated: child_text(&entry, "updated")
+                    .or_else(|| child_text(&entry, "modified"))
+                    .and_then(|text| parse_datetime(&text)),
+                author: atom_author(&entry).or_else(|| feed_author.clone()),
+                categories: child_elements(&entry)
+                    .filter(|node| name_of(node) == "category")
+                    .filter_map(|node| {
+                        attribute(&node, "label").or_else(|| attribute(&node, "term"))
+                    })
+                    .collect(),
+                summary_html: atom_text(&entry, "summary", base.as_ref()),
+                content_html: atom_text(&entry, "content", base.as_ref()),
+            }
+        })
+        .collect()
+}
+
+/// Content of an Atom text construct as HTML, honouring its `type`
+fn atom_text(node: &NodeRef, name: &str, base: Option<&Url>) -> Option<String> {
+    let element = child_elements(node).find(|child| name_of(child) == name)?;
+    let html = match attribute(&element, "type").as_deref() {
+        Some("xhtml") => child_elements(&element)
+            .flat_map(|div| {
+                // The XHTML namespace is the HTML one, so this serializes without xmlns
+                div.children()
+                    .map(|child| child.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<String>(),
+        Some("html") | Some("text/html") => element.text_contents(),
+        _ => escape(&element.text_contents()),
+    };
+    let html = html.trim();
+    (!html.is_empty()).then(|| absolutize(html, base))
+}
+
+/// The `alternate` link of an Atom feed or entry
+fn atom_link(node: &NodeRef) -> Option<String> {
+    child_elements(node)
+        .filter(|child| name_of(child) == "link")
+        .find(|link| {
+            attribute(link, "rel")
+                .map(|rel| rel == "alternate")
+                .unwrap_or(true)
+        })
+        .and_then(|link| attribute(&link, "href"))
+}
+
+fn atom_author(node: &NodeRef) -> Option<String> {
+    child_elements(node)
+        .filter(|child| name_of(child) == "author")
+        .find_map(|author| child_text(&author, "name").or_else(|| child_text(&author, "email")))
+}
+
+fn xml_base(node: &NodeRef, parent: Option<&Url>) -> Option<Url> {
+    attribute(node, "base").and_then(|base| Url::parse(&resolve_url(parent, &base)).ok())
+}
+
+fn parse_json_feed(content: &str, base: Option<Url>) -> Result<Vec<FeedItem>, Box<dyn Error>> {
+    let feed: Value = serde_json::from_str(content)?;
+    if !feed["version"]
+        .as_str()
+        .is_some_and(|version| version.contains("jsonfeed.org"))
+    {
+        return Err("JSON document is not a JSON Feed".into());
+    }
+
+    let base = feed["home_page_url"]
+        .as_str()
+        .and_then(|url| Url::parse(url).ok())
+        .or(base);
+    let feed_author = json_author(&feed);
+    let items = feed["items"].as_array().cloned().unwrap_or_default();
+
+    Ok(items
+        .iter()
+        .map(|item| {
+            let link = json_string(&item["url"])
+                .or_else(|| json_string(&item["external_url"]))
+                .map(|link| resolve_url(base.as_ref(), &link));
+            FeedItem {
+                id: json_string(&item["id"]).or_else(|| link.clone()),
+                title: json_string(&item["title"]),
+                link,
+                published: json_string(&item["date_published"])
+                    .and_then(|text| parse_datetime(&text)),
+                updated: json_string(&item["date_modified"]).and_then(|text| parse_datetime(&text)),
+                author: json_author(item).or_else(|| feed_author.clone()),
+                categories: item["tags"]
+                    .as_array()
+                    .map(|tags| tags.iter().filter_map(json_string).collect())
+                    .unwrap_or_default(),
+                summary_html: json_string(&item["summary"]).map(|summary| escape(&summary)),
+                content_html: json_string(&item["content_html"])
+                    .or_else(|| json_string(&item["content_text"]).map(|text| escape(&text)))
+                    .map(|html| absolutize(&html, base.as_ref())),
+            }
+        })
+        .collect())
+}
+
+/// Author name of a JSON Feed 1.1 (`authors`) or 1.0 (`author`) object
+fn json_author(value: &Value) -> Option<String> {
+    value["authors"]
+        .as_array()
+        .and_then(|authors| {
+            authors
+                .iter()
+                .find_map(|author| json_string(&author["name"]))
+        })
+        .or_else(|| json_string(&value["author"]["name"]))
+}
+
+fn json_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+fn absolutize(html: &str, base: Option<&Url>) -> String {
+    match base {
+        Some(base) => absolutize_links(html, base),
+        None => html.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    htmlescape::encode_minimal(text)
+}
+
+/// Non-empty texts of all child elements with one of the given local names
+fn child_texts(node: &NodeRef, names: &[&str]) -> Vec<String> {
+    child_elements(node)
+        .filter(|child| names.contains(&name_of(child).as_str()))
+        .map(|child| child.text_contents().trim().to_string())
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel>
+    <title>Jobs</title>
+    <link>https://example.com/careers/</link>
+    <item>
+      <title>Rust Engineer</title>
+      <link>jobs/1</link>
+      <guid isPermaLink="false">job-1</guid>
+      <pubDate>Mon, 06 Jan 2025 10:00:00 GMT</pubDate>
+      <dc:creator>Jane</dc:creator>
+      <category>Engineering</category>
+      <category>Remote</category>
+      <description><![CDATA[<p>Join <a href="/team">us</a></p>]]></description>
+      <content:encoded><![CDATA[<ul><li>Rust</li></ul>]]></content:encoded>
+    </item>
+  </channel>
+</rss>"#;
+
+    const RDF: &str = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/" xmlns:dc="http://purl.org/dc/elements/1.1/">
+  <channel rdf:about="https://example.com/rss"><link>https://example.com/</link></channel>
+  <item rdf:about="https://example.com/jobs/2">
+    <title>Data Engineer</title>
+    <link>https://example.com/jobs/2</link>
+    <dc:date>2025-01-07T09:00:00Z</dc:date>
+    <dc:subject>Data</dc:subject>
+  </item>
+</rdf:RDF>"#;
+
+    const ATOM: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+  <link rel="alternate" href="https://example.com/"/>
+  <author><name>Careers Team</name></author>
+  <entry>
+    <id>tag:example.com,2025:3</id>
+    <title type="html">Senior &lt;b&gt;Rust&lt;/b&gt; Engineer</title>
+    <link rel="self" href="https://example.com/feed/3"/>
+    <link rel="alternate" href="/jobs/3"/>
+    <published>2025-01-08T08:00:00+01:00</published>
+    <updated>2025-01-09T08:00:00Z</updated>
+    <category term="eng" label="Engineering"/>
+    <summary>Fish &amp; chips</summary>
+    <content type="xhtml"><div xmlns="http://www.w3.org/1999/xhtml"><p>Apply <a href="apply">here</a></p></div></content>
+  </entry>
+</feed>"#;
+
+    const JSON_FEED: &str = r#"{
+  "version": "https://jsonfeed.org/version/1.1",
+  "home_page_url": "https://example.com/",
+  "authors": [{"name": "Careers Team"}],
+  "items": [
+    {"id": 4, "url": "/jobs/4", "title": "Designer", "content_text": "Figma & more",
+     "date_published": "2025-01-10T10:00:00Z", "tags": ["Design"]}
+  ]
+}"#;
+
+    #[test]
+    fn test_rss() {
+        let items = parse_feed(RSS, None).unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.id.as_deref(), Some("job-1"));
+        assert_eq!(item.title.as_deref(), Some("Rust Engineer"));
+        assert_eq!(
+            item.link.as_deref(),
+            Some("https://example.com/careers/jobs/1")
+        );
+        assert_eq!(
+            item.published.unwrap().to_rfc3339(),
+            "2025-01-06T10:00:00+00:00"
+        );
+        assert_eq!(item.author.as_deref(), Some("Jane"));
+        assert_eq!(item.categories, vec!["Engineering", "Remote"]);
+        assert_eq!(
+            item.summary_html.as_deref(),
+            Some(r#"<p>Join <a href="https://example.com/team">us</a></p>"#)
+        );
+        assert_eq!(item.content_html.as_deref(), Some("<ul><li>Rust</li></ul>"));
+    }
+
+    #[test]
+    fn test_rss1_rdf() {
+        let items = parse_feed(RDF, None).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id.as_deref(), Some("https://example.com/jobs/2"));
+        assert_eq!(items[0].title.as_deref(), Some("Data Engineer"));
+        assert!(items[0].published.is_some());
+        assert_eq!(items[0].categories, vec!["Data"]);
+    }
+
+    #[test]
+    fn test_atom() {
+        let items = parse_feed(ATOM, None).unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.id.as_deref(), Some("tag:example.com,2025:3"));
+        assert_eq!(item.title.as_deref(), Some("Senior Rust Engineer"));
+        assert_eq!(item.link.as_deref(), Some("https://example.com/jobs/3"));
+        assert_eq!(
+            item.published.unwrap().to_rfc3339(),
+            "2025-01-08T07:00:00+00:00"
+        );
+        assert!(item.updated.is_some());
+        assert_eq!(item.author.as_deref(), Some("Careers Team"));
+        assert_eq!(item.categories, vec!["Engineering"]);
+        assert_eq!(item.summary_html.as_deref(), Some("Fish &amp; chips"));
+        assert_eq!(
+            item.content_html.as_deref(),
+            Some(r#"<p>Apply <a href="https://example.com/apply">here</a></p>"#)
+        );
+    }
+
+    #[test]
+    fn test_json_feed() {
+        let items = parse_feed(JSON_FEED, None).unwrap();
+        assert_eq!(items.len(), 1);
+        let item = &items[0];
+        assert_eq!(item.id.as_deref(), Some("4"));
+        assert_eq!(item.link.as_deref(), Some("https://example.com/jobs/4"));
+        assert_eq!(item.author.as_deref(), Some("Careers Team"));
+        assert_eq!(item.categories, vec!["Design"]);
+        assert_eq!(item.content_html.as_deref(), Some("Figma &amp; more"));
+    }
+
+    #[test]
+    fn test_base_url_fallback() {
+        let rss = "<rss><channel><item><link>/jobs/5</link></item></channel></rss>";
+        let items = parse_feed(rss, Some("https://example.org/")).unwrap();
+        assert_eq!(items[0].link.as_deref(), Some("https://example.org/jobs/5"));
+    }
+
+    #[test]
+    fn test_not_a_feed() {
+        assert!(parse_feed("<html><body></body></html>", None).is_err());
+        assert!(parse_feed(r#"{"items": []}"#, None).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+use crate::datetime::parse_datetime;
+use crate::jsonld::{jsonld_nodes, nodes_of_type};
+use crate::microdata::microdata_items;
+use crate::rdfa::rdfa_items;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// Address of a `jobLocation` Place
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JobLocation {
+    /// Name of the place, or the address when it is given as plain text
+    pub name: Option<String>,
+    pub street_address: Option<String>,
+    pub locality: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+/// `baseSalary` as a range, `min` and `max` being equal for a single value
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Salary {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub currency: Option<String>,
+    /// `unitText` such as `HOUR`, `MONTH` or `YEAR`
+    pub unit: Option<String>,
+}
+
+/// A schema.org JobPosting, normalized from JSON-LD, microdata or RDFa
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct JobPosting {
+    /// Where the posting was found: `json-ld`, `microdata` or `rdfa`
+    pub source: String,
+    pub title: Option<String>,
+    pub organization_name: Option<String>,
+    pub organization_url: Option<String>,
+    pub organization_logo: Option<String>,
+    pub date_posted: Option<DateTime<Utc>>,
+    pub valid_through: Option<DateTime<Utc>>,
+    pub employment_type: Vec<String>,
+    pub locations: Vec<JobLocation>,
+    pub applicant_location_requirements: Vec<String>,
+    pub job_location_type: Option<String>,
+    /// Whether `jobLocationType` is `TELECOMMUTE`
+    pub is_remote: bool,
+    pub salary: Option<Salary>,
+    pub direct_apply: Option<bool>,
+    pub description_html: Option<String>,
+}
+
+/// Values of a property, a single value or an array
+pub(crate) fn values(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(values)) => values.iter().collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(value) => vec![value],
+    }
+}
+
+/// Text of a value: a string, a number, or the `name` or `@value` of an object
+pub(crate) fn text(value: Option<&Value>) -> Option<String> {
+    values(value).into_iter().find_map(|value| {
+        let text = match value {
+            Value::String(text) => text.trim().to_string(),
+            Value::Number(number) => number.to_string(),
+            Value::Object(object) => return text(object.get("name").or(object.get("@value"))),
+            _ => return None,
+        };
+        (!text.is_empty()).then_some(text)
+    })
+}
+
+/// URL of a value: a string, or the `url`, `contentUrl` or `@id` of an object
+fn url(value: Option<&Value>) -> Option<String> {
+    values(value).into_iter().find_map(|value| match value {
+        Value::Object(object) => text(
+            object
+                .get("url")
+                .or(object.get("contentUrl"))
+                .or(object.get("@id")),
+        ),
+        value => text(Some(value)),
+    })
+}
+
+/// Number of a value, also from strings such as "50,000" or "50000.00"
+fn number(value: Option<&Value>) -> Option<f64> {
+    match value? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().replace([',', ' ', '\u{a0}'], "").parse().ok(),
+        Value::Object(object) => number(object.get("@value")),
+        _ => None,
+    }
+}
+
+fn location(value: &Value) -> JobLocation {
+    let Value::Object(place) = value else {
+        return JobLocation {
+            name: text(Some(value)),
+            ..Default::default()
+        };
+    };
+    let mut location = JobLocation {
+        name: text(place.get("name")),
+        ..Default::default()
+    };
+    match place.get("address") {
+        Some(Value::Object(address)) => {
+            location.street_address = text(address.get("streetAddress"));
+            location.locality = text(address.get("addressLocality"));
+            location.region = text(address.get("addressRegion"));
+            location.postal_code = text(address.get("postalCode"));
+            location.country = text(address.get("addressCountry"));
+        }
+        address => {
+            location.name = location.name.or_else(|| text(address));
+        }
+    }
+    location
+}
+
+fn salary(value: Option<&Value>) -> Option<Salary> {
+    let value = values(value).into_iter().next()?;
+    let Value::Object(amount) = value else {
+        let amount = number(Some(value))?;
+        return Some(Salary {
+            min: Some(amount),
+            max: Some(amount),
+            ..Default::default()
+        });
+    };
+
+    let mut salary = Salary {
+        currency: text(amount.get("currency")),
+        ..Default::default()
+    };
+    match amount.get("value") {
+        Some(Value::Object(quantity)) => {
+            let single = number(quantity.get("value"));
+            salary.min = number(quantity.get("minValue")).or(single);
+            salary.max = number(quantity.get("maxValue")).or(single);
+            salary.unit = text(quantity.get("unitText"));
+        }
+        value => {
+            salary.min = number(value);
+            salary.max = salary.min;
+        }
+    }
+    salary.unit = salary.unit.or_else(|| text(amount.get("unitText")));
+    (salary.min.is_some() || salary.max.is_some()).then_some(salary)
+}
+
+fn boolean(value: Option<&Value>) -> Option<bool> {
+    match value? {
+        Value::Bool(flag) => Some(*flag),
+        Value::String(text) => match text.trim().to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" | "https://schema.org/true" | "http://schema.org/true" => {
+                Some(true)
+            }
+            "false" | "no" | "0" | "https://schema.org/false" | "http://schema.org/false" => {
+                Some(false)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Normalize a JobPosting node
+pub fn job_posting(node: &Value, source: &str) -> JobPosting {
+    let organization = node.get("hiringOrganization");
+    let organization_object = values(organization)
+        .into_iter()
+        .find(|value| value.is_object());
+
+    let employment_type = values(node.get("employmentType"))
+        .into_iter()
+        .filter_map(Value::as_str)
+        .flat_map(|types| types.split(','))
+        .map(|kind| kind.trim().to_string())
+        .filter(|kind| !kind.is_empty())
+        .collect();
+
+    let job_location_type = text(node.get("jobLocationType"));
+    JobPosting {
+        source: source.to_string(),
+        title: text(node.get("title")).or_else(|| text(node.get("name"))),
+        organization_name: text(organization),
+        organization_url: organization_object
+            .and_then(|org| url(org.get("url")).or_else(|| url(org.get("sameAs")))),
+        organization_logo: organization_object.and_then(|org| url(org.get("logo"))),
+        date_posted: text(node.get("datePosted")).and_then(|date| parse_datetime(&date)),
+        valid_through: text(node.get("validThrough")).and_then(|date| parse_datetime(&date)),
+        employment_type,
+        locations: values(node.get("jobLocation"))
+            .into_iter()
+            .map(location)
+            .collect(),
+        applicant_location_requirements: values(node.get("applicantLocationRequirements"))
+            .into_iter()
+            .filter_map(|value| text(Some(value)))
+            .collect(),
+        is_remote: job_location_type
+            .as_deref()
+            .is_some_and(|kind| kind.to_ascii_uppercase().contains("TELECOMMUTE")),
+        job_location_type,
+        salary: salary(node.get("baseSalary")),
+        direct_apply: boolean(node.get("directApply")),
+        description_html: text(node.get("description")),
+    }
+}
+
+/// Find the first JobPosting of a document, in its JSON-LD, else its microdata, else its
+/// RDFa, and normalize it
+pub fn html_job_posting(html: &str) -> Option<JobPosting> {
+    if let Some(node) = jsonld_nodes(html, Some("JobPosting")).first() {
+        return Some(job_posting(node, "json-ld"));
+    }
+    for (source, items) in [
+        ("microdata", microdata_items(html)),
+        ("rdfa", rdfa_items(html)),
+    ] {
+        if let Some(node) = nodes_of_type(&items, "JobPosting").first() {
+            return Some(job_posting(node, source));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_ld_job_posting() {
+        let posting = html_job_posting(
+            r#"<script type="application/ld+json">{
+                "@context": "https://schema.org",
+                "@type": "JobPosting",
+                "title": "Rust Developer",
+                "description": "&lt;p&gt;Build &lt;b&gt;things&lt;/b&gt;&lt;/p&gt;",
+                "datePosted": "2024-05-01",
+                "validThrough": "2024-06-30T23:59:00+02:00",
+                "employmentType": ["FULL_TIME", "CONTRACTOR"],
+                "hiringOrganization": {"@type": "Organization", "name": "Acme", "sameAs": "https://acme.example", "logo": {"@type": "ImageObject", "url": "https://acme.example/logo.png"}},
+                "jobLocation": [
+                    {"@type": "Place", "address": {"@type": "PostalAddress", "streetAddress": "1 Main St", "addressLocality": "Berlin", "postalCode": "10115", "addressCountry": {"@type": "Country", "name": "DE"}}},
+                    {"@type": "Place", "address": "Munich, Germany"}
+                ],
+                "applicantLocationRequirements": [{"@type": "Country", "name": "Germany"}, {"@type": "Country", "name": "Austria"}],
+                "jobLocationType": "TELECOMMUTE",
+                "baseSalary": {"@type": "MonetaryAmount", "currency": "EUR", "value": {"@type": "QuantitativeValue", "minValue": "60,000", "maxValue": 80000, "unitText": "YEAR"}},
+                "directApply": "True"
+            }</script>"#,
+        )
+        .unwrap();
+
+        assert_eq!(posting.source, "json-ld");
+        assert_eq!(posting.title.as_deref(), Some("Rust Developer"));
+        assert_eq!(
+            posting.description_html.as_deref(),
+            Some("<p>Build <b>things</b></p>")
+        );
+        assert_eq!(posting.organization_name.as_deref(), Some("Acme"));
+        assert_eq!(
+            posting.organization_url.as_deref(),
+            Some("https://acme.example")
+        );
+        assert_eq!(
+            posting.organization_logo.as_deref(),
+            Some("https://acme.example/logo.png")
+        );
+        assert_eq!(
+            posting.date_posted.unwrap().to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            posting.valid_through.unwrap().to_rfc3339(),
+            "2024-06-30T21:59:00+00:00"
+        );
+        assert_eq!(posting.employment_type, vec!["FULL_TIME", "CONTRACTOR"]);
+        assert_eq!(
+            posting.locations,
+            vec![
+                JobLocation {
+                    street_address: Some("1 Main St".into()),
+                    locality: Some("Berlin".into()),
+                    postal_code: Some("10115".into()),
+                    country: Some("DE".into()),
+                    ..Default::default()
+                },
+                JobLocation {
+                    name: Some("Munich, Germany".into()),
+                    ..Default::default()
+                },
+            ]
+        );
+        assert_eq!(
+            posting.applicant_location_requirements,
+            vec!["Germany", "Austria"]
+        );
+        assert!(posting.is_remote);
+        assert_eq!(
+            posting.salary,
+            Some(Salary {
+                min: Some(60000.0),
+                max: Some(80000.0),
+                currency: Some("EUR".into()),
+                unit: Some("YEAR".into()),
+            })
+        );
+        assert_eq!(posting.direct_apply, Some(true));
+    }
+
+    #[test]
+    fn test_microdata_and_rdfa_fallbacks() {
+        let posting = html_job_posting(
+            r#"<div itemscope itemtype="https://schema.org/JobPosting">
+                 <h1 itemprop="title">Clerk</h1>
+                 <span itemprop="employmentType">PART_TIME, TEMPORARY</span>
+                 <span itemprop="hiringOrganization">City Hall</span>
+                 <div itemprop="baseSalary" itemscope itemtype="https://schema.org/MonetaryAmount">
+                   <meta itemprop="currency" content="USD"><span itemprop="value">25</span>
+                   <meta itemprop="unitText" content="HOUR">
+                 </div>
+               </div>"#,
+        )
+        .unwrap();
+        assert_eq!(posting.source, "microdata");
+        assert_eq!(posting.employment_type, vec!["PART_TIME", "TEMPORARY"]);
+        assert_eq!(posting.organization_name.as_deref(), Some("City Hall"));
+        assert_eq!(
+            posting.salary,
+            Some(Salary {
+                min: Some(25.0),
+                max: Some(25.0),
+                currency: Some("USD".into()),
+                unit: Some("HOUR".into()),
+            })
+        );
+        assert!(!posting.is_remote);
+
+        let posting = html_job_posting(
+            r#"<div vocab="https://schema.org/" typeof="JobPosting"><h1 property="title">Lecturer</h1></div>"#,
+        )
+        .unwrap();
+        assert_eq!(posting.source, "rdfa");
+        assert_eq!(posting.title.as_deref(), Some("Lecturer"));
+
+        assert_eq!(html_job_posting("<p>No posting</p>"), None);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{namespace, parse_document, selector, QueryOptions};
+use html5ever::{namespace_url, ns, LocalName, Namespace, QualName};
+use kuchikiki::{ExpandedName, NodeData, NodeRef};
+use serde_json::{json, Map, Value};
+use std::error::Error;
+
+/// Convert a node to its JSON tree form
+///
+/// Elements become `{"tag", "attrs", "children"}` objects, text nodes plain strings and
+/// comments `{"comment"}` objects. Template contents are treated as children. Doctypes
+/// and processing instructions are left out.
+pub fn node_to_json(node: &NodeRef) -> Option<Value> {
+    match node.data() {
+        NodeData::Element(element) => {
+            let attrs: Map<String, Value> = element
+                .attributes
+                .borrow()
+                .map
+                .iter()
+                .map(|(name, attr)| {
+                    let name = match &attr.prefix {
+                        Some(prefix) => format!("{}:{}", prefix, name.local),
+                        None => name.local.to_string(),
+                    };
+                    (name, Value::String(attr.value.clone()))
+                })
+                .collect();
+            let parent = element.template_contents.as_ref().unwrap_or(node);
+            let children: Vec<Value> = parent
+                .children()
+                .filter_map(|child| node_to_json(&child))
+                .collect();
+            Some(json!({
+                "tag": element.name.local.to_string(),
+                "attrs": attrs,
+                "children": children,
+            }))
+        }
+        NodeData::Text(text) => Some(Value::String(text.borrow().clone())),
+        NodeData::Comment(text) => Some(json!({ "comment": text.borrow().clone() })),
+        _ => None,
+    }
+}
+
+/// Convert the first element matching `selector`, or the root `<html>` element, to a JSON
+/// tree, see [`node_to_json`]
+pub fn html_to_json(html: &str, selector: Option<&str>) -> Result<Option<String>, Box<dyn Error>> {
+    let options = QueryOptions::default();
+    let document = parse_document(html, &options);
+    let node = match selector.filter(|selector| !selector.trim().is_empty()) {
+        Some(selector) => selector::select_nodes(&document, selector, &options)?
+            .into_iter()
+            .next(),
+        None => document
+            .children()
+            .find(|child| child.as_element().is_some()),
+    };
+    Ok(node
+        .and_then(|node| node_to_json(&node))
+        .map(|value| value.to_string()))
+}
+
+/// Whether a name can be written into a tag as is: no whitespace, control characters,
+/// quotes or `<`, `>`, `/`, `=`, which would end the name and start other markup
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| {
+            c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '<' | '>' | '/' | '=')
+        })
+}
+
+/// Elements whose text is serialized unescaped
+const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "script",
+    "style",
+    "xmp",
+    "iframe",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+];
+
+fn element_name(tag: &str, parent_ns: &Namespace) -> QualName {
+    let ns = match tag.to_ascii_lowercase().as_str() {
+        "svg" => ns!(svg),
+        "math" => ns!(mathml),
+        // Only SVG's <foreignObject> and MathML's annotations switch back to HTML, which
+        // doesn't change how anything is serialized
+        _ => parent_ns.clone(),
+    };
+    QualName::new(None, ns, LocalName::from(tag))
+}
+
+fn json_to_node(value: &Value, parent_ns: &Namespace) -> Result<NodeRef, Box<dyn Error>> {
+    let object = match value {
+        Value::String(text) => return Ok(NodeRef::new_text(text.as_str())),
+        Value::Object(object) => object,
+        other => return Err(format!("Expected a string or an object, got {}", other).into()),
+    };
+
+    if let Some(comment) = object.get("comment") {
+        let text = comment.as_str().ok_or("Comment must be a string")?;
+        // Comment text is written as is, so these would end the comment early
+        if text.contains("--") || text.contains('>') {
+            return Err(format!("Invalid comment '{}'", text).into());
+        }
+        return Ok(NodeRef::new_comment(text));
+    }
+
+    let tag = object
+        .get("tag")
+        .and_then(Value::as_str)
+        .filter(|tag| !tag.is_empty())
+        .ok_or("Element objects need a 'tag'")?;
+    if !tag.starts_with(|c: char| c.is_ascii_alphabetic()) || !is_valid_name(tag) {
+        return Err(format!("Invalid tag name '{}'", tag).into());
+    }
+    let name = element_name(tag, parent_ns);
+
+    let mut attributes = Vec::new();
+    if let Some(attrs) = object.get("attrs").filter(|attrs| !attrs.is_null()) {
+        let attrs = attrs.as_object().ok_or("'attrs' must be an object")?;
+        for (attr_name, value) in attrs {
+            if !is_valid_name(attr_name) {
+                return Err(format!("Invalid attribute name '{}'", attr_name).into());
+            }
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Null => continue,
+                other => other.to_string(),
+            };
+            let (prefix, ns, local) = match attr_name.split_once(':') {
+                Some((prefix, local)) => match namespace::well_known_namespace(prefix) {
+                    Some(ns) => (Some(prefix.into()), ns, local),
+                    None => (None, ns!(), attr_name.as_str()),
+                },
+                None => (None, ns!(), attr_name.as_str()),
+            };
+            attributes.push((
+                ExpandedName::new(ns, LocalName::from(local)),
+                kuchikiki::Attribute { prefix, value },
+            ));
+        }
+    }
+
+    // Children go into the element itself, including for templates, so they serialize
+    let element = NodeRef::new_element(name.clone(), attributes);
+    if let Some(children) = object
+        .get("children")
+        .filter(|children| !children.is_null())
+    {
+        let children = children.as_array().ok_or("'children' must be an array")?;
+        let lower_tag = tag.to_ascii_lowercase();
+        let end_tag = format!("</{}", lower_tag);
+        let is_raw_text = name.ns == ns!(html) && RAW_TEXT_ELEMENTS.contains(&lower_tag.as_str());
+        for child in children {
+            // Text of raw text elements is written as is, so an end tag in it would close
+            // the element and let the rest be parsed as markup
+            if let Some(text) = child.as_str().filter(|_| is_raw_text) {
+                if text.to_ascii_lowercase().contains(&end_tag) {
+                    return Err(format!("Text of <{}> can't contain '{}'", tag, end_tag).into());
+                }
+            }
+            element.append(json_to_node(child, &name.ns)?);
+        }
+    }
+    Ok(element)
+}
+
+/// Serialize a JSON tree as produced by [`html_to_json`] back to HTML
+///
+/// Accepts a single node or an array of nodes. Text and attribute values are escaped,
+/// void elements get no end tag. Tag and attribute names that could not be written as
+/// such, like `img src=x`, are an error, as are comments containing `--` or `>` and text
+/// that would end a `<script>`, `<style>` or other raw text element.
+pub fn json_to_html(json: &str) -> Result<String, Box<dyn Error>> {
+    let value: Value = serde_json::from_str(json)?;
+    let nodes = match &value {
+        Value::Array(nodes) => nodes.iter().collect(),
+        node => vec![node],
+    };
+
+    let mut html = String::new();
+    for node in nodes {
+        html.push_str(&json_to_node(node, &ns!(html))?.to_string());
+    }
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_json() {
+        let json = html_to_json(
+            "<div id=job class='a b'>Dev &amp; Ops<br><!-- note --></div>",
+            Some("#job"),
+        )
+        .unwrap()
+        .unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "tag": "div",
+                "attrs": {"id": "job", "class": "a b"},
+                "children": [
+                    "Dev & Ops",
+                    {"tag": "br", "attrs": {}, "children": []},
+                    {"comment": " note "}
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_html_to_json_defaults_to_root() {
+        let json = html_to_json("<p>Hi</p>", None).unwrap().unwrap();
+        assert!(
+            json.starts_with(r#"{"attrs":{},"children":[{"attrs":{},"children":[],"tag":"head"}"#)
+        );
+        assert_eq!(html_to_json("<p>Hi</p>", Some("table")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_json_to_html_escapes() {
+        let html = json_to_html(
+            r#"{"tag": "a", "attrs": {"href": "/jobs?a=1&b=\"2\""}, "children": ["<b> & more", {"tag": "img", "attrs": {"src": "x.png"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            html,
+            r#"<a href="/jobs?a=1&amp;b=&quot;2&quot;">&lt;b&gt; &amp; more<img src="x.png"></a>"#
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = r##"<ul class="jobs"><li>One</li><!--x--><li><svg><use xlink:href="#i"></use></svg></li></ul>"##;
+        let json = html_to_json(source, Some("ul")).unwrap().unwrap();
+        assert_eq!(json_to_html(&json).unwrap(), source);
+
+        let template = html_to_json("<template><p>Inside</p></template>", Some("template"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            json_to_html(&template).unwrap(),
+            "<template><p>Inside</p></template>"
+        );
+    }
+
+    #[test]
+    fn test_json_to_html_fragments_and_errors() {
+        assert_eq!(
+            json_to_html(r#"["a", {"tag": "b", "children": ["c"]}]"#).unwrap(),
+            "a<b>c</b>"
+        );
+        assert!(json_to_html(r#"{"attrs": {}}"#).is_err());
+        assert!(json_to_html("not json").is_err());
+    }
+
+    #[test]
+    fn test_json_to_html_rejects_invalid_names() {
+        assert!(json_to_html(r#"{"tag": "img src=x onerror=alert(1)"}"#).is_err());
+        assert!(json_to_html(r#"{"tag": "p><script"}"#).is_err());
+        assert!(json_to_html(r#"{"tag": "1p"}"#).is_err());
+        assert!(json_to_html(r#"{"tag": "p", "attrs": {"a onclick=alert(2) b": "v"}}"#).is_err());
+        assert!(json_to_html(r#"{"tag": "p", "attrs": {"x"y": "v"}}"#).is_err());
+        assert_eq!(
+            json_to_html(r#"{"tag": "my-widget", "attrs": {"@click": "go", "data-x": "1"}}"#)
+                .unwrap(),
+            r#"<my-widget @click="go" data-x="1"></my-widget>"#
+        );
+    }
+
+    #[test]
+    fn test_json_to_html_rejects_comment_and_raw_text_breakouts() {
+        assert!(json_to_html(r#"{"comment": "--><img src=x onerror=alert(1)><!--"}"#).is_err());
+        assert!(json_to_html(
+            r#"{"tag": "style", "children": ["</style><img src=x onerror=alert(1)>"]}"#
+        )
+        .is_err());
+        assert!(json_to_html(r#"{"tag": "SCRIPT", "children": ["x</Script >alert(1)"]}"#).is_err());
+        assert_eq!(
+            json_to_html(
+                r#"[{"tag": "script", "children": ["if (a < b) { f('</p>') }"]}, {"comment": " note "}]"#
+            )
+            .unwrap(),
+            "<script>if (a < b) { f('</p>') }</script><!-- note -->"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::microdata::SCHEMA_ORG;
+use crate::{decode_entities, escape_json_control_chars, parse_document, QueryOptions};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+
+/// Parse a JSON-LD script, escaping raw control characters in strings if needed, and
+/// decode HTML entities left in its strings
+fn parse_block(text: &str) -> Option<Value> {
+    let text = text.trim();
+    let value = serde_json::from_str(text)
+        .or_else(|_| serde_json::from_str(&escape_json_control_chars(text)))
+        .ok()?;
+    Some(decode_strings(value))
+}
+
+fn decode_strings(value: Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(decode_entities(&text)),
+        Value::Array(values) => Value::Array(values.into_iter().map(decode_strings).collect()),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, decode_strings(value)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+/// Top-level nodes of a block: the items of arrays and `@graph`s
+fn flatten(value: Value, nodes: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                flatten(value, nodes);
+            }
+        }
+        Value::Object(mut object) => {
+            let Some(graph) = object.remove("@graph") else {
+                nodes.push(Value::Object(object));
+                return;
+            };
+            flatten(graph, nodes);
+            // A graph's container can be a node itself, beyond its @context
+            if object.keys().any(|key| key != "@context") {
+                nodes.push(Value::Object(object));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn id_of(value: &Value) -> Option<&str> {
+    value.get("@id").and_then(Value::as_str)
+}
+
+/// Whether a node is only a `{"@id": ...}` reference to a node defined elsewhere
+fn is_reference(object: &Map<String, Value>) -> bool {
+    object.len() == 1 && object.contains_key("@id")
+}
+
+/// Collect the definitions of identified nodes, at any depth, merging the properties of
+/// nodes defined more than once
+fn collect_definitions(value: &Value, definitions: &mut HashMap<String, Map<String, Value>>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_definitions(value, definitions);
+            }
+        }
+        Value::Object(object) => {
+            if let Some(id) = id_of(value).filter(|_| !is_reference(object)) {
+                let definition = definitions.entry(id.to_string()).or_default();
+                for (key, value) in object {
+                    definition
+                        .entry(key.clone())
+                        .or_insert_with(|| value.clone());
+                }
+            }
+            for value in object.values() {
+                collect_definitions(value, definitions);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replace identified nodes by their merged definitions, inlining references. Each
+/// node is expanded once per top-level node; later references to it, including those
+/// back to a node being inlined, stay `{"@id": ...}` so shared nodes can't blow up the
+/// output
+fn inline(
+    value: &Value,
+    definitions: &HashMap<String, Map<String, Value>>,
+    expanded: &mut HashSet<String>,
+) -> Value {
+    match value {
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| inline(value, definitions, expanded))
+                .collect(),
+        ),
+        Value::Object(object) => {
+            let id = id_of(value);
+            if let Some(id) = id {
+                if !expanded.insert(id.to_string()) {
+                    return json!({ "@id": id });
+                }
+            }
+            let object = id.and_then(|id| definitions.get(id)).unwrap_or(object);
+            Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), inline(value, definitions, expanded)))
+                    .collect(),
+            )
+        }
+        other => other.clone(),
+    }
+}
+
+/// Type name without a schema.org prefix, `schema:JobPosting` -> `JobPosting`
+fn compact_type(name: &str) -> &str {
+    SCHEMA_ORG
+        .iter()
+        .chain(&["schema:"])
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+}
+
+/// Whether a node's `@type`, a string or an array, includes `wanted`
+pub(crate) fn has_type(node: &Value, wanted: &str) -> bool {
+    let wanted = compact_type(wanted);
+    let matches = |value: &Value| {
+        value
+            .as_str()
+            .is_some_and(|name| compact_type(name).eq_ignore_ascii_case(wanted))
+    };
+    match node.get("@type") {
+        Some(Value::Array(types)) => types.iter().any(matches),
+        Some(value) => matches(value),
+        None => false,
+    }
+}
+
+/// Nodes of type `wanted` in document order, outermost first
+fn find_typed(value: &Value, wanted: &str, found: &mut Vec<Value>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                find_typed(value, wanted, found);
+            }
+        }
+        Value::Object(object) => {
+            if has_type(value, wanted) {
+                found.push(value.clone());
+                return;
+            }
+            for value in object.values() {
+                find_typed(value, wanted, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Add a node unless the same node, or a node with the same `@id`, is already there
+fn push_unique(nodes: &mut Vec<Value>, node: Value) {
+    let duplicate = nodes.iter().any(|existing| {
+        *existing == node || id_of(existing).is_some_and(|id| id_of(&node) == Some(id))
+    });
+    if !duplicate {
+        nodes.push(node);
+    }
+}
+
+/// Normalized JSON-LD nodes of all `application/ld+json` scripts of a document
+///
+/// `@graph`s and arrays are flattened, identified nodes are merged across scripts and
+/// inlined where they are referenced by `@id`, and identical nodes are kept once. With
+/// `node_type`, only the nodes whose `@type` (a string or an array) includes it are
+/// returned, including nested ones; schema.org prefixes are ignored when comparing.
+pub fn jsonld_nodes(html: &str, node_type: Option<&str>) -> Vec<Value> {
+    let document = parse_document(html, &QueryOptions::default());
+    let mut nodes = Vec::new();
+    for script in document.descendants().filter(|node| {
+        node.as_element().is_some_and(|element| {
+            &*element.name.local == "script"
+                && element
+                    .attributes
+                    .borrow()
+                    .get("type")
+                    .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/ld+json"))
+        })
+    }) {
+        if let Some(value) = parse_block(&script.text_contents()) {
+            flatten(value, &mut nodes);
+        }
+    }
+
+    let mut definitions = HashMap::new();
+    for node in &nodes {
+        collect_definitions(node, &mut definitions);
+    }
+
+    let mut normalized = Vec::new();
+    for node in &nodes {
+        push_unique(
+            &mut normalized,
+            inline(node, &definitions, &mut HashSet::new()),
+        );
+    }
+
+    match node_type.map(str::trim).filter(|wanted| !wanted.is_empty()) {
+        Some(wanted) => nodes_of_type(&normalized, wanted),
+        None => normalized,
+    }
+}
+
+/// Nodes of `nodes`, or nested in them, whose `@type` includes `wanted`, kept once each
+pub fn nodes_of_type(nodes: &[Value], wanted: &str) -> Vec<Value> {
+    let mut found = Vec::new();
+    for node in nodes {
+        find_typed(node, wanted, &mut found);
+    }
+    let mut unique = Vec::new();
+    for node in found {
+        push_unique(&mut unique, node);
+    }
+    unique
+}
+
+/// [`jsonld_nodes`] as a JSON array
+pub fn html_jsonld(html: &str, node_type: Option<&str>) -> String {
+    Value::Array(jsonld_nodes(html, node_type)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn script(json: &str) -> String {
+        format!(r#"<script type="application/ld+json">{}</script>"#, json)
+    }
+
+    #[test]
+    fn test_graph_flattening_and_references() {
+        let html = script(
+            r##"{"@context": "https://schema.org", "@graph": [
+                {"@type": "JobPosting", "@id": "#job", "title": "Dev", "hiringOrganization": {"@id": "#org"}},
+                {"@type": "Organization", "@id": "#org", "name": "Acme"}
+            ]}"##,
+        );
+        assert_eq!(
+            jsonld_nodes(&html, Some("JobPosting")),
+            vec![json!({
+                "@type": "JobPosting",
+                "@id": "#job",
+                "title": "Dev",
+                "hiringOrganization": {"@type": "Organization", "@id": "#org", "name": "Acme"}
+            })]
+        );
+    }
+
+    #[test]
+    fn test_dedup_across_scripts() {
+        let html = [
+            script(r#"{"@type": "JobPosting", "title": "Dev"}"#),
+            script(r#"[{"@type": "JobPosting", "title": "Dev"}, {"@type": "WebPage"}]"#),
+            script(r#"{"@type": ["JobPosting", "Thing"], "@id": "j2", "title": "Ops"}"#),
+            script(r#"{"@id": "j2", "datePosted": "2024-05-01"}"#),
+        ]
+        .concat();
+        assert_eq!(
+            jsonld_nodes(&html, Some("schema:JobPosting")),
+            vec![
+                json!({"@type": "JobPosting", "title": "Dev"}),
+                json!({"@type": ["JobPosting", "Thing"], "@id": "j2", "title": "Ops", "datePosted": "2024-05-01"}),
+            ]
+        );
+        assert_eq!(jsonld_nodes(&html, None).len(), 3);
+    }
+
+    #[test]
+    fn test_nested_matches_and_cycles() {
+        let html = script(
+            r#"{"@type": "WebPage", "@id": "p", "mainEntity": {"@type": "https://schema.org/JobPosting", "title": "Dev", "page": {"@id": "p"}}}"#,
+        );
+        assert_eq!(
+            jsonld_nodes(&html, Some("JobPosting")),
+            vec![
+                json!({"@type": "https://schema.org/JobPosting", "title": "Dev", "page": {"@id": "p"}})
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_nodes_expanded_once() {
+        // Each node references the next one twice, which would double the output at
+        // every level if every reference were expanded
+        let graph: Vec<String> = (0..40)
+            .map(|n| {
+                format!(
+                    r#"{{"@id": "n{n}", "name": "{n}", "a": {{"@id": "n{next}"}}, "b": {{"@id": "n{next}"}}}}"#,
+                    next = n + 1
+                )
+            })
+            .collect();
+        let html = script(&format!(r#"{{"@graph": [{}]}}"#, graph.join(",")));
+        let nodes = jsonld_nodes(&html, None);
+        assert_eq!(nodes.len(), 40);
+        assert_eq!(nodes[38]["a"]["name"], json!("39"));
+        assert_eq!(nodes[38]["b"], json!({"@id": "n39"}));
+        assert!(nodes[0].to_string().len() < 10_000);
+    }
+
+    #[test]
+    fn test_malformed_scripts() {
+        let html = [
+            script("{\"@type\": \"JobPosting\", \"description\": \"Line\none &amp; two\"}"),
+            script("not json"),
+            "<script>{\"@type\": \"JobPosting\"}</script>".to_string(),
+        ]
+        .concat();
+        assert_eq!(
+            html_jsonld(&html, Some("jobposting")),
+            r#"[{"@type":"JobPosting","description":"Line\none & two"}]"#
+        );
+        assert_eq!(html_jsonld("<p>None</p>", None), "[]");
+    }
+}
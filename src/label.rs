@@ -0,0 +1,356 @@
+use crate::{collapse_whitespace, name_of, parse_document, selector, QueryOptions};
+use kuchikiki::NodeRef;
+use std::error::Error;
+
+/// Inline elements used to set a label apart, as in `<strong>Type:</strong> Full-time`
+const LABEL_ELEMENTS: &[&str] = &["b", "strong", "label", "span", "em"];
+
+/// Elements that can be part of a value following a label
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "code", "em", "i", "span", "strong", "time",
+];
+
+/// Elements whose whole text may be a `Label: value` line
+const LINE_ELEMENTS: &[&str] = &["li", "p", "div", "span", "td"];
+
+/// Longest label, in characters, accepted from free text
+const MAX_LABEL_LEN: usize = 60;
+
+/// Lowest similarity for a fuzzy label match
+const MIN_SIMILARITY: f64 = 0.75;
+
+fn text(node: &NodeRef) -> String {
+    collapse_whitespace(&node.text_contents())
+}
+
+/// Normalize a label into a lowercase snake_case key: "Job Type:" -> "job_type"
+pub fn normalize_label(label: &str) -> String {
+    label
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+struct Collector {
+    pairs: Vec<(String, String)>,
+}
+
+impl Collector {
+    /// Keep the first value of each label
+    fn add(&mut self, label: &str, value: &str) {
+        let key = normalize_label(label);
+        let value = collapse_whitespace(value);
+        let value = value.trim_start_matches(':').trim();
+        if key.is_empty()
+            || value.is_empty()
+            || label.chars().count() > MAX_LABEL_LEN
+            || self.pairs.iter().any(|(existing, _)| *existing == key)
+        {
+            return;
+        }
+        self.pairs.push((key, value.to_string()));
+    }
+}
+
+/// `<dt>` labels with the `<dd>` values following them
+fn definition_lists(root: &NodeRef, collector: &mut Collector) {
+    for list in root
+        .inclusive_descendants()
+        .filter(|node| name_of(node) == "dl")
+    {
+        // Entries may be wrapped in <div>s
+        let entries = list
+            .children()
+            .flat_map(|child| match name_of(&child).as_str() {
+                "div" => child.children().collect::<Vec<_>>(),
+                _ => vec![child],
+            });
+
+        let mut labels: Vec<String> = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+        let mut flush = |labels: &mut Vec<String>, values: &mut Vec<String>| {
+            for label in labels.drain(..) {
+                collector.add(&label, &values.join(", "));
+            }
+            values.clear();
+        };
+        for entry in entries {
+            match name_of(&entry).as_str() {
+                "dt" => {
+                    if !values.is_empty() {
+                        flush(&mut labels, &mut values);
+                    }
+                    labels.push(text(&entry));
+                }
+                "dd" => values.push(text(&entry)),
+                _ => {}
+            }
+        }
+        flush(&mut labels, &mut values);
+    }
+}
+
+/// Table rows of a `<th>` label followed by `<td>` values
+fn header_cells(root: &NodeRef, collector: &mut Collector) {
+    for row in root
+        .inclusive_descendants()
+        .filter(|node| name_of(node) == "tr")
+    {
+        let cells: Vec<NodeRef> = row
+            .children()
+            .filter(|cell| matches!(name_of(cell).as_str(), "th" | "td"))
+            .collect();
+        let [label, values @ ..] = cells.as_slice() else {
+            continue;
+        };
+        if name_of(label) != "th"
+            || values.is_empty()
+            || values.iter().any(|cell| name_of(cell) != "td")
+        {
+            continue;
+        }
+        let values: Vec<String> = values.iter().map(text).collect();
+        collector.add(&text(label), &values.join(", "));
+    }
+}
+
+fn is_label_element(node: &NodeRef) -> bool {
+    LABEL_ELEMENTS.contains(&name_of(node).as_str())
+}
+
+/// `<strong>Label:</strong> value` runs, the value ending at a `<br>`, a block or the next
+/// label
+fn inline_labels(root: &NodeRef, collector: &mut Collector) {
+    for label in root.inclusive_descendants().filter(is_label_element) {
+        let label_text = text(&label);
+        let next_text = label
+            .next_sibling()
+            .and_then(|next| {
+                next.as_text()
+                    .map(|text| text.borrow().trim_start().to_string())
+            })
+            .unwrap_or_default();
+        let label_text = match label_text.strip_suffix(':') {
+            Some(stripped) => stripped.trim_end().to_string(),
+            None if next_text.starts_with(':') => label_text,
+            None => continue,
+        };
+
+        let mut value = String::new();
+        for sibling in label.following_siblings() {
+            if let Some(chunk) = sibling.as_text() {
+                value.push_str(&chunk.borrow());
+                continue;
+            }
+            let is_next_label = is_label_element(&sibling) && text(&sibling).ends_with(':');
+            let is_inline = INLINE_ELEMENTS.contains(&name_of(&sibling).as_str());
+            if is_next_label || !is_inline {
+                break;
+            }
+            value.push(' ');
+            value.push_str(&sibling.text_contents());
+        }
+        collector.add(&label_text, &value);
+    }
+}
+
+/// Elements whose whole text is a `Label: value` line
+fn text_lines(root: &NodeRef, collector: &mut Collector) {
+    for element in root.inclusive_descendants().filter(|node| {
+        LINE_ELEMENTS.contains(&name_of(node).as_str())
+            && node.children().all(|child| {
+                child.as_text().is_some() || INLINE_ELEMENTS.contains(&name_of(&child).as_str())
+            })
+    }) {
+        let line = text(&element);
+        let Some((label, value)) = line.split_once(':') else {
+            continue;
+        };
+        // "10:30" and "https://" are not labels
+        let is_label = label.chars().any(char::is_alphabetic)
+            && !label.ends_with(|c: char| c.is_ascii_digit())
+            && value.starts_with(char::is_whitespace)
+            && label.split_whitespace().count() <= 5;
+        if is_label {
+            collector.add(label, value);
+        }
+    }
+}
+
+/// Label/value pairs from definition lists, `<th>`/`<td>` rows and `Label: value` markup
+/// below `root`, with normalized labels, keeping the first value of each label
+pub fn label_values(root: &NodeRef) -> Vec<(String, String)> {
+    let mut collector = Collector { pairs: Vec::new() };
+    definition_lists(root, &mut collector);
+    header_cells(root, &mut collector);
+    inline_labels(root, &mut collector);
+    text_lines(root, &mut collector);
+    collector.pairs
+}
+
+/// Label/value pairs of the document, or of the elements matching `selector`
+pub fn html_label_values(
+    html: &str,
+    selector: Option<&str>,
+) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let options = QueryOptions::default();
+    let document = parse_document(html, &options);
+    let roots = match selector.filter(|selector| !selector.trim().is_empty()) {
+        Some(selector) => selector::select_nodes(&document, selector, &options)?,
+        None => vec![document],
+    };
+
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for (key, value) in roots.iter().flat_map(label_values) {
+        if !pairs.iter().any(|(existing, _)| *existing == key) {
+            pairs.push((key, value));
+        }
+    }
+    Ok(pairs)
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// How well a normalized key matches a normalized query, from 0 to 1
+fn similarity(key: &str, query: &str) -> f64 {
+    if key == query {
+        return 1.0;
+    }
+    let key_words: Vec<&str> = key.split('_').collect();
+    let query_words: Vec<&str> = query.split('_').collect();
+    if query_words.iter().all(|word| key_words.contains(word)) {
+        return 0.9;
+    }
+    if key_words.iter().all(|word| query_words.contains(word)) {
+        return 0.8;
+    }
+    let key: Vec<char> = key.chars().collect();
+    let query: Vec<char> = query.chars().collect();
+    let longest = key.len().max(query.len()).max(1);
+    1.0 - levenshtein(&key, &query) as f64 / longest as f64
+}
+
+/// Value of the label best matching `label`: the exact label, one containing all its
+/// words ("Salary" finds "Annual salary"), one whose words are all in it, or a close
+/// spelling ("Locaton"). Parts of words don't count, "Age" doesn't find "Language"
+pub fn html_label_value(html: &str, label: &str) -> Option<String> {
+    let query = normalize_label(label);
+    if query.is_empty() {
+        return None;
+    }
+    let pairs = html_label_values(html, None).ok()?;
+
+    let mut best: Option<(f64, String)> = None;
+    for (key, value) in pairs {
+        let score = similarity(&key, &query);
+        if score >= MIN_SIMILARITY && best.as_ref().is_none_or(|(best, _)| score > *best) {
+            best = Some((score, value));
+        }
+    }
+    best.map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(html: &str) -> Vec<(String, String)> {
+        html_label_values(html, None).unwrap()
+    }
+
+    fn pair(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(normalize_label(" Job Type: "), "job_type");
+        assert_eq!(normalize_label("Gehalt (brutto)"), "gehalt_brutto");
+    }
+
+    #[test]
+    fn test_definition_lists() {
+        assert_eq!(
+            pairs("<dl><dt>Location</dt><dd>Berlin</dd><dd>Remote</dd><div><dt>Team</dt><dd>Data</dd></div></dl>"),
+            vec![pair("location", "Berlin, Remote"), pair("team", "Data")]
+        );
+    }
+
+    #[test]
+    fn test_header_cells() {
+        assert_eq!(
+            pairs(
+                "<table><tr><th>Salary</th><td>60k</td></tr><tr><th>A</th><th>B</th></tr></table>"
+            ),
+            vec![pair("salary", "60k")]
+        );
+    }
+
+    #[test]
+    fn test_inline_labels() {
+        assert_eq!(
+            pairs("<p><strong>Type:</strong> Full-time<br><b>Start</b>: <a href='#'>ASAP</a></p>"),
+            vec![pair("type", "Full-time"), pair("start", "ASAP")]
+        );
+    }
+
+    #[test]
+    fn test_text_lines() {
+        assert_eq!(
+            pairs(
+                "<ul><li>Location: Berlin</li><li>Opens at 10:30</li><li>See https://x.y</li></ul>"
+            ),
+            vec![pair("location", "Berlin")]
+        );
+    }
+
+    #[test]
+    fn test_selector_scope() {
+        let html = "<div id=a><dl><dt>X</dt><dd>1</dd></dl></div><div id=b><dl><dt>Y</dt><dd>2</dd></dl></div>";
+        assert_eq!(
+            html_label_values(html, Some("#b")).unwrap(),
+            vec![pair("y", "2")]
+        );
+    }
+
+    #[test]
+    fn test_fuzzy_lookup() {
+        let html = "<dl><dt>Annual salary</dt><dd>60k</dd><dt>Location</dt><dd>Berlin</dd></dl>";
+        assert_eq!(html_label_value(html, "Salary").as_deref(), Some("60k"));
+        assert_eq!(
+            html_label_value(html, "location:").as_deref(),
+            Some("Berlin")
+        );
+        assert_eq!(html_label_value(html, "Locaton").as_deref(), Some("Berlin"));
+        assert_eq!(html_label_value(html, "Benefits"), None);
+        assert_eq!(
+            html_label_value("<dl><dt>Salary</dt><dd>60k</dd></dl>", "Annual salary").as_deref(),
+            Some("60k")
+        );
+    }
+
+    #[test]
+    fn test_no_partial_word_matches() {
+        assert_eq!(
+            html_label_value("<dl><dt>Language</dt><dd>German</dd></dl>", "Age"),
+            None
+        );
+        assert_eq!(
+            html_label_value("<dl><dt>A</dt><dd>1</dd></dl>", "Salary"),
+            None
+        );
+    }
+}
@@ -1,10 +1,36 @@
+pub mod ats;
+pub mod dates;
+pub mod datetime;
+pub mod feed;
+pub mod job_posting;
 pub mod js_decode;
+pub mod json_tree;
+pub mod jsonld;
+pub mod label;
 pub mod link;
+pub mod list;
+pub mod meta;
+pub mod microdata;
+pub mod microformats;
+pub mod namespace;
+pub mod offer;
 pub mod pretty_print;
+pub mod rdfa;
+pub mod salary;
+pub mod selector;
+pub mod sink;
+pub mod sitemap;
+pub mod source;
+pub mod table;
+pub mod template;
+pub mod tree;
+pub mod validate;
+pub mod xml;
 
 #[cfg(feature = "duckdb")]
 pub mod duckdb;
 
+use html5ever::{local_name, namespace_url, ns, LocalName, QualName};
 use kuchikiki::traits::{NodeIterator, TendrilSink};
 use kuchikiki::NodeRef;
 use std::error::Error;
@@ -88,27 +114,40 @@ pub fn extract_all_text(html: &str, selector: &str) -> Result<Vec<String>, Box<d
 }
 
 /// Extraction mode for html_query functions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ExtractMode {
     /// Return full HTML of element
     Html,
     /// Return text content only
     Text,
-    /// Return specific attribute value
+    /// Return specific attribute value (`xlink:href` style names select namespaced attributes)
     Attribute(String),
     /// Return multiple attributes as JSON object
     MultiAttribute(Vec<String>),
+    /// Return the location of the element in the input as a JSON object
+    SourceSpan,
+    /// Return the element exactly as written in the input
+    SourceHtml,
+    /// Return the salary in the text content as a JSON object, see [`salary::parse_salary`]
+    Salary,
 }
 
 impl ExtractMode {
     /// Parse extraction mode from optional attribute string
     /// None or empty -> Html
     /// "@text" or "text" -> Text
+    /// "@source_span" -> SourceSpan, "@source_html" -> SourceHtml
+    /// "@salary" -> Salary
     /// "@attr" -> Attribute(attr)
+    /// The special modes shadow attributes of the same name, which are still read without
+    /// the `@`: "salary" -> Attribute("salary")
     pub fn from_attr(attr: Option<&str>) -> Self {
         match attr {
             None | Some("") => ExtractMode::Html,
             Some("@text") | Some("text") => ExtractMode::Text,
+            Some("@source_span") => ExtractMode::SourceSpan,
+            Some("@source_html") => ExtractMode::SourceHtml,
+            Some("@salary") => ExtractMode::Salary,
             Some(s) if s.starts_with('@') => ExtractMode::Attribute(s[1..].to_string()),
             Some(s) => ExtractMode::Attribute(s.to_string()),
         }
@@ -153,51 +192,263 @@ pub fn extract_all_elements(
     extract_all_with_mode(html, selector, &mode)
 }
 
-/// Extract all elements matching selector with specified extraction mode
-pub fn extract_all_with_mode(
-    html: &str,
-    selector: &str,
-    mode: &ExtractMode,
-) -> Result<Vec<String>, Box<dyn Error>> {
-    let document = kuchikiki::parse_html().one(html);
-    let mut results = Vec::new();
+/// Parser used for the input of html_query functions
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum ParserKind {
+    /// HTML5 tree builder (html5ever)
+    #[default]
+    Html,
+    /// XML parser (xml5ever), for feeds, sitemaps, SVG and XHTML documents
+    Xml,
+}
 
-    for node in document
-        .select(selector)
-        .map_err(|_| "Failed to parse CSS selector")?
-    {
-        let content = match mode {
-            ExtractMode::Text => serialize_text(node.as_node(), false).trim().to_string(),
-            ExtractMode::Html => node.as_node().to_string(),
-            ExtractMode::Attribute(attr) => {
-                if let Some(element) = node.as_node().as_element() {
-                    if let Ok(attrs) = element.attributes.try_borrow() {
-                        attrs.get(attr.as_str()).unwrap_or("").to_string()
-                    } else {
-                        String::new()
+/// Options controlling how html_query functions parse and traverse documents
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryOptions {
+    /// Parser used for the input
+    pub parser: ParserKind,
+    /// Also match inside `<template>` contents and declarative shadow roots
+    pub templates: bool,
+    /// Parse the input as a fragment inside this context element (e.g. "tr", "ul", "svg:g")
+    /// instead of as a full document
+    pub context: Option<String>,
+}
+
+impl QueryOptions {
+    /// Parse options from a comma-separated list of `key` or `key=value` entries
+    ///
+    /// - "templates": descend into template contents and shadow roots
+    /// - "context=tr": parse the input as a fragment inside a `<tr>` element
+    /// - "parser=xml": parse the input as XML instead of HTML
+    pub fn parse(options: &str) -> Result<Self, Box<dyn Error>> {
+        Self::default().apply(options)
+    }
+
+    /// Default options for the xml_query functions
+    pub fn xml() -> Self {
+        Self {
+            parser: ParserKind::Xml,
+            ..Default::default()
+        }
+    }
+
+    /// Override these options with a comma-separated list of entries, see [`QueryOptions::parse`]
+    pub fn apply(self, options: &str) -> Result<Self, Box<dyn Error>> {
+        let mut parsed = self;
+
+        for entry in options.split(',') {
+            let entry = entry.trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            let (key, value) = match entry.split_once('=') {
+                Some((key, value)) => (key.trim(), Some(value.trim())),
+                None => (entry, None),
+            };
+
+            match key.to_ascii_lowercase().as_str() {
+                "templates" | "shadow" => parsed.templates = parse_flag(key, value)?,
+                "context" => match value {
+                    Some(value) if !value.is_empty() => parsed.context = Some(value.to_string()),
+                    _ => return Err("Option 'context' requires an element name".into()),
+                },
+                "parser" => {
+                    parsed.parser = match value.map(|v| v.to_ascii_lowercase()).as_deref() {
+                        Some("html") => ParserKind::Html,
+                        Some("xml") => ParserKind::Xml,
+                        _ => return Err("Option 'parser' must be 'html' or 'xml'".into()),
                     }
+                }
+                _ => return Err(format!("Unknown option '{}'", key).into()),
+            }
+        }
+
+        if parsed.parser == ParserKind::Xml && parsed.context.is_some() {
+            return Err("Option 'context' is only supported by the HTML parser".into());
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn parse_flag(key: &str, value: Option<&str>) -> Result<bool, Box<dyn Error>> {
+    match value.map(|v| v.to_ascii_lowercase()).as_deref() {
+        None | Some("true") | Some("1") | Some("on") => Ok(true),
+        Some("false") | Some("0") | Some("off") => Ok(false),
+        Some(other) => Err(format!("Invalid value '{}' for option '{}'", other, key).into()),
+    }
+}
+
+/// Decode HTML entities, returning the input unchanged if it contains invalid entities
+pub fn decode_entities(input: &str) -> String {
+    htmlescape::decode_html(input).unwrap_or_else(|_| input.to_string())
+}
+
+/// Value of an element's attribute, found like `@attr` extraction finds it (see
+/// [`namespace::attribute_value`]); `None` for other nodes
+pub(crate) fn attribute(node: &NodeRef, name: &str) -> Option<String> {
+    let element = node.as_element()?;
+    let attrs = element.attributes.borrow();
+    namespace::attribute_value(&attrs, name)
+}
+
+/// Local name of an element, empty for other nodes
+pub(crate) fn name_of(node: &NodeRef) -> String {
+    node.as_element()
+        .map(|element| element.name.local.to_string())
+        .unwrap_or_default()
+}
+
+/// Text with runs of whitespace collapsed to single spaces and the ends trimmed
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse an HTML document according to the query options
+pub fn parse_document(html: &str, options: &QueryOptions) -> NodeRef {
+    if options.parser == ParserKind::Xml {
+        return xml::parse_xml(html);
+    }
+
+    if let Some(context) = &options.context {
+        return parse_fragment(html, context, options);
+    }
+
+    let document = kuchikiki::parse_html().one(html);
+
+    if options.templates {
+        template::inline_templates(&document);
+    }
+
+    document
+}
+
+/// Parse an HTML fragment as the children of a `context` element
+///
+/// The context is an HTML element name such as "body" or "tr", or a `prefix:name` for
+/// foreign content such as "svg:g". It decides which tags the tree builder accepts, so
+/// e.g. orphan `<td>` cells survive with a "tr" context. The parsed nodes are wrapped in
+/// an `<html>` element, as in html5ever's fragment parsing algorithm.
+pub fn parse_fragment(html: &str, context: &str, options: &QueryOptions) -> NodeRef {
+    let document = kuchikiki::parse_fragment(context_name(context), Vec::new()).one(html);
+
+    if options.templates {
+        template::inline_templates(&document);
+    }
+
+    document
+}
+
+/// Qualified name of a fragment context given as "tr", "svg" or "svg:g"
+pub(crate) fn context_name(context: &str) -> QualName {
+    match context.split_once(':') {
+        Some((prefix, local)) => QualName::new(
+            None,
+            namespace::well_known_namespace(prefix).unwrap_or(ns!(html)),
+            LocalName::from(local),
+        ),
+        None if context.eq_ignore_ascii_case("svg") => {
+            QualName::new(None, ns!(svg), local_name!("svg"))
+        }
+        None if context.eq_ignore_ascii_case("math") => {
+            QualName::new(None, ns!(mathml), local_name!("math"))
+        }
+        None => QualName::new(
+            None,
+            ns!(html),
+            LocalName::from(context.to_ascii_lowercase()),
+        ),
+    }
+}
+
+/// Extract the content of a single matched node according to the extraction mode
+pub fn extract_content(node: &NodeRef, mode: &ExtractMode) -> String {
+    match mode {
+        ExtractMode::Text => serialize_text(node, false).trim().to_string(),
+        ExtractMode::Salary => salary::parse_salary(&serialize_text(node, false), None)
+            .map(|salary| salary.to_json())
+            .unwrap_or_default(),
+        ExtractMode::Html => node.to_string(),
+        ExtractMode::Attribute(attr) => {
+            if let Some(element) = node.as_element() {
+                if let Ok(attrs) = element.attributes.try_borrow() {
+                    namespace::attribute_value(&attrs, attr).unwrap_or_default()
                 } else {
                     String::new()
                 }
+            } else {
+                String::new()
             }
-            ExtractMode::MultiAttribute(attr_list) => {
-                let mut obj = serde_json::Map::new();
-                if let Some(element) = node.as_node().as_element() {
-                    if let Ok(attrs) = element.attributes.try_borrow() {
-                        for attr in attr_list {
-                            let value = if attr == "@text" {
-                                serialize_text(node.as_node(), false).trim().to_string()
-                            } else {
-                                attrs.get(attr.as_str()).unwrap_or("").to_string()
-                            };
-                            // Use "text" as key for @text
-                            let key = if attr == "@text" { "text" } else { attr };
-                            obj.insert(key.to_string(), serde_json::Value::String(value));
-                        }
+        }
+        ExtractMode::MultiAttribute(attr_list) => {
+            let mut obj = serde_json::Map::new();
+            if let Some(element) = node.as_element() {
+                if let Ok(attrs) = element.attributes.try_borrow() {
+                    for attr in attr_list {
+                        let value = if attr == "@text" {
+                            serialize_text(node, false).trim().to_string()
+                        } else {
+                            namespace::attribute_value(&attrs, attr).unwrap_or_default()
+                        };
+                        // Use "text" as key for @text
+                        let key = if attr == "@text" { "text" } else { attr };
+                        obj.insert(key.to_string(), serde_json::Value::String(value));
                     }
                 }
-                serde_json::to_string(&obj).unwrap_or_default()
             }
+            serde_json::to_string(&obj).unwrap_or_default()
+        }
+        // Source positions are only known while the document is parsed
+        ExtractMode::SourceSpan | ExtractMode::SourceHtml => String::new(),
+    }
+}
+
+/// Extract all elements matching selector with specified extraction mode
+pub fn extract_all_with_mode(
+    html: &str,
+    selector: &str,
+    mode: &ExtractMode,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    extract_all_with_options(html, selector, mode, &QueryOptions::default())
+}
+
+/// Extract all elements matching selector with specified extraction mode and query options
+///
+/// A trailing `::text` or `::attr(name)` pseudo-element on the selector is used as the
+/// extraction mode when `mode` is `Html` (i.e. no explicit extract argument was given).
+pub fn extract_all_with_options(
+    html: &str,
+    selector: &str,
+    mode: &ExtractMode,
+    options: &QueryOptions,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let (selector, pseudo_mode) = selector::split_pseudo_element(selector)?;
+    let mode = match (mode, pseudo_mode) {
+        (ExtractMode::Html, Some(pseudo_mode)) => pseudo_mode,
+        (mode, _) => mode.clone(),
+    };
+
+    let (document, sources) = match mode {
+        ExtractMode::SourceSpan | ExtractMode::SourceHtml => {
+            let (document, sources) = source::parse_with_spans(html, options)?;
+            (document, Some(sources))
+        }
+        _ => (parse_document(html, options), None),
+    };
+    let mut results = Vec::new();
+
+    for node in selector::select_nodes(&document, &selector, options)? {
+        let content = match (&mode, options.parser, &sources) {
+            (ExtractMode::Html, ParserKind::Xml, _) => xml::to_xml_string(&node),
+            (ExtractMode::SourceSpan, _, Some(sources)) => sources
+                .span(&node)
+                .map(|span| span.to_json())
+                .unwrap_or_default(),
+            (ExtractMode::SourceHtml, _, Some(sources)) => {
+                sources.source_html(&node).unwrap_or_default().to_string()
+            }
+            _ => extract_content(&node, &mode),
         };
         if !content.is_empty() {
             results.push(content);
@@ -316,3 +567,116 @@ pub fn escape_json_control_chars(input: &str) -> String {
 
     fixed
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(html: &str, selector: &str, options: &str) -> Vec<String> {
+        let options = QueryOptions::parse(options).unwrap();
+        extract_all_with_options(html, selector, &ExtractMode::Text, &options).unwrap()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = QueryOptions::parse("templates, context=tr").unwrap();
+        assert!(options.templates);
+        assert_eq!(options.context.as_deref(), Some("tr"));
+
+        assert_eq!(QueryOptions::parse("").unwrap(), QueryOptions::default());
+        assert!(!QueryOptions::parse("templates=false").unwrap().templates);
+    }
+
+    #[test]
+    fn test_parse_invalid_options() {
+        assert!(QueryOptions::parse("unknown").is_err());
+        assert!(QueryOptions::parse("templates=maybe").is_err());
+        assert!(QueryOptions::parse("context").is_err());
+        assert!(QueryOptions::parse("parser=json").is_err());
+        assert!(QueryOptions::parse("parser=xml, context=tr").is_err());
+    }
+
+    #[test]
+    fn test_xml_parser_option() {
+        let options = QueryOptions::xml().apply("templates").unwrap();
+        assert_eq!(options.parser, ParserKind::Xml);
+        assert_eq!(
+            QueryOptions::parse("parser=xml").unwrap().parser,
+            ParserKind::Xml
+        );
+
+        let rss = "<rss><item><link>https://example.com/1</link></item></rss>";
+        assert!(extract(rss, "item > link", "").is_empty());
+        assert_eq!(
+            extract(rss, "item > link", "parser=xml"),
+            vec!["https://example.com/1"]
+        );
+
+        let html = extract_all_with_options(rss, "link", &ExtractMode::Html, &options).unwrap();
+        assert_eq!(html, vec!["<link>https://example.com/1</link>"]);
+    }
+
+    #[test]
+    fn test_orphan_cells_dropped_without_context() {
+        assert!(extract("<td>A</td><td>B</td>", "td", "").is_empty());
+    }
+
+    #[test]
+    fn test_fragment_context_keeps_orphan_elements() {
+        assert_eq!(
+            extract("<td>A</td><td>B</td>", "td", "context=tr"),
+            vec!["A", "B"]
+        );
+        assert_eq!(
+            extract("<tr><td>A</td></tr>", "tr > td", "context=tbody"),
+            vec!["A"]
+        );
+        assert_eq!(
+            extract("<li>One</li><li>Two</li>", "li", "context=ul"),
+            vec!["One", "Two"]
+        );
+    }
+
+    #[test]
+    fn test_fragment_context_foreign_content() {
+        let options = QueryOptions::parse("context=svg").unwrap();
+        let document = parse_document("<circle r='1'/>", &options);
+        let circle = document.select_first("circle").unwrap();
+        assert_eq!(circle.name.ns, ns!(svg));
+    }
+
+    #[test]
+    fn test_source_extract_modes() {
+        let html = "<ul>\n  <LI class=a>One\n  <li>Two</li>\n</ul>";
+        let options = QueryOptions::default();
+        let source = ExtractMode::from_attr(Some("@source_html"));
+        assert_eq!(
+            extract_all_with_options(html, "li", &source, &options).unwrap(),
+            vec!["<LI class=a>One\n  ", "<li>Two</li>"]
+        );
+        let span = ExtractMode::from_attr(Some("@source_span"));
+        assert_eq!(
+            extract_all_with_options(html, "li:last-child", &span, &options).unwrap(),
+            vec![r#"{"offset":25,"length":12,"line":3,"column":3}"#]
+        );
+        assert!(extract_all_with_options(html, "li", &span, &QueryOptions::xml()).is_err());
+    }
+
+    #[test]
+    fn test_salary_extract_mode() {
+        let html =
+            r#"<p class="salary">ca. 4.500&nbsp;€ / Monat</p><p class="salary">Competitive</p>"#;
+        let mode = ExtractMode::from_attr(Some("@salary"));
+        assert_eq!(mode, ExtractMode::Salary);
+        assert_eq!(
+            ExtractMode::from_attr(Some("salary")),
+            ExtractMode::Attribute("salary".to_string())
+        );
+        assert_eq!(
+            extract_all_with_mode(html, ".salary", &mode).unwrap(),
+            vec![
+                r#"{"currency":"EUR","is_estimate":true,"max":4500.0,"min":4500.0,"period":"MONTH"}"#
+            ]
+        );
+    }
+}
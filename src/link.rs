@@ -44,6 +44,26 @@ pub fn detect_base(document: &NodeRef) -> Option<Url> {
     None
 }
 
+/// Resolve a possibly relative URL against an optional base
+pub fn resolve_url(base: Option<&Url>, href: &str) -> String {
+    let href = href.trim();
+    base.and_then(|base| base.join(href).ok())
+        .map(String::from)
+        .unwrap_or_else(|| href.to_string())
+}
+
+/// Rewrite the relative links of an HTML fragment against `base`
+pub fn absolutize_links(html: &str, base: &Url) -> String {
+    let fragment = crate::parse_fragment(html, "body", &Default::default());
+    for node in fragment.descendants() {
+        rewrite_relative_url(&node, base);
+    }
+    fragment
+        .first_child()
+        .map(|root| root.children().map(|child| child.to_string()).collect())
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 mod tests {
     use html5ever::tendril::TendrilSink;
@@ -119,6 +139,25 @@ mod tests {
         ),
     }
 
+    #[test]
+    fn test_resolve_url() {
+        let base = Url::parse("https://mgdm.net/blog/").unwrap();
+        assert_eq!(
+            resolve_url(Some(&base), "post"),
+            "https://mgdm.net/blog/post"
+        );
+        assert_eq!(resolve_url(None, " post "), "post");
+    }
+
+    #[test]
+    fn test_absolutize_links() {
+        let base = Url::parse("https://mgdm.net/blog/").unwrap();
+        assert_eq!(
+            absolutize_links("<p>See <a href=\"post\">this</a></p>", &base),
+            "<p>See <a href=\"https://mgdm.net/blog/post\">this</a></p>"
+        );
+    }
+
     detect_base_tests! {
         base_ok: (
             "<html><head><base href=\"https://example.org\"></head><body><a href=\"https://example.org/foo/bar\">Hello</a></body></html>".to_string(),
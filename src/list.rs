@@ -0,0 +1,161 @@
+use crate::{collapse_whitespace, parse_document, selector, QueryOptions};
+use html5ever::local_name;
+use kuchikiki::iter::NodeEdge;
+use kuchikiki::NodeRef;
+use serde_json::{json, Value};
+use std::error::Error;
+
+fn is_list(node: &NodeRef) -> bool {
+    node.as_element().is_some_and(|element| {
+        matches!(
+            element.name.local,
+            local_name!("ul") | local_name!("ol") | local_name!("menu")
+        )
+    })
+}
+
+fn is_item(node: &NodeRef) -> bool {
+    node.as_element()
+        .is_some_and(|element| element.name.local == local_name!("li"))
+}
+
+/// Text and link targets of an item, leaving out the lists nested in it
+fn own_content(item: &NodeRef) -> (String, Vec<String>) {
+    let mut text = String::new();
+    let mut links = Vec::new();
+    // Depth inside nested lists, whose content belongs to the child items
+    let mut nested = 0;
+
+    for edge in item.traverse() {
+        match edge {
+            NodeEdge::Start(node) if is_list(&node) => nested += 1,
+            NodeEdge::End(node) if is_list(&node) => nested -= 1,
+            NodeEdge::Start(node) if nested == 0 => {
+                if let Some(chunk) = node.as_text() {
+                    text.push_str(&chunk.borrow());
+                } else if let Some(element) = node.as_element() {
+                    if element.name.local == local_name!("a") {
+                        if let Some(href) = element.attributes.borrow().get(local_name!("href")) {
+                            links.push(href.to_string());
+                        }
+                    } else if element.name.local == local_name!("br") {
+                        text.push(' ');
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (collapse_whitespace(&text), links)
+}
+
+/// Convert the items of a list into JSON, recursing into the lists nested in each item
+fn list_items(list: &NodeRef) -> Vec<Value> {
+    list.children()
+        .filter(is_item)
+        .filter_map(|item| {
+            let (text, links) = own_content(&item);
+            let children: Vec<Value> = item
+                .descendants()
+                .filter(|node| {
+                    is_list(node)
+                        && node
+                            .ancestors()
+                            .take_while(|ancestor| *ancestor != item)
+                            .all(|ancestor| !is_list(&ancestor))
+                })
+                .flat_map(|nested| list_items(&nested))
+                .collect();
+            if text.is_empty() && links.is_empty() && children.is_empty() {
+                return None;
+            }
+            Some(json!({
+                "text": text,
+                "links": links,
+                "children": children,
+            }))
+        })
+        .collect()
+}
+
+/// Convert the list matched by `selector` (or the first list inside the matched element)
+/// into a JSON array of `{"text", "links", "children"}` items
+///
+/// `text` is the item's own text without its nested lists, which become `children`.
+pub fn html_list(html: &str, selector: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let options = QueryOptions::default();
+    let document = parse_document(html, &options);
+    let list = selector::select_nodes(&document, selector, &options)?
+        .into_iter()
+        .find_map(|node| {
+            if is_list(&node) {
+                Some(node)
+            } else {
+                node.descendants().find(is_list)
+            }
+        });
+    Ok(list.map(|list| Value::Array(list_items(&list)).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(html: &str, selector: &str) -> Value {
+        serde_json::from_str(&html_list(html, selector).unwrap().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let value = list(
+            "<ul class=req>
+               <li>Languages
+                 <ul><li>Rust</li><li>Go, <a href='/go'>see why</a></li></ul>
+               </li>
+               <li><b>5+</b> years</li>
+             </ul>",
+            ".req",
+        );
+        assert_eq!(
+            value,
+            json!([
+                {
+                    "text": "Languages",
+                    "links": [],
+                    "children": [
+                        {"text": "Rust", "links": [], "children": []},
+                        {"text": "Go, see why", "links": ["/go"], "children": []}
+                    ]
+                },
+                {"text": "5+ years", "links": [], "children": []}
+            ])
+        );
+    }
+
+    #[test]
+    fn test_list_inside_matched_element() {
+        let value = list(
+            "<section id=benefits><h2>Benefits</h2><ol><li>Remote</li><li></li><li>Gym</li></ol></section>",
+            "#benefits",
+        );
+        assert_eq!(
+            value,
+            json!([
+                {"text": "Remote", "links": [], "children": []},
+                {"text": "Gym", "links": [], "children": []}
+            ])
+        );
+        assert_eq!(html_list("<p>No list</p>", "p").unwrap(), None);
+    }
+
+    #[test]
+    fn test_lists_wrapped_in_item_markup() {
+        let value = list(
+            "<ul><li><div>Cloud <div><ul><li>AWS</li></ul></div></div></li></ul>",
+            "ul",
+        );
+        assert_eq!(value[0]["text"], "Cloud");
+        assert_eq!(value[0]["children"][0]["text"], "AWS");
+    }
+}
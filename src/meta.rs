@@ -0,0 +1,213 @@
+use crate::link::{detect_base, resolve_url};
+use crate::{collapse_whitespace, parse_document, QueryOptions};
+use html5ever::{local_name, namespace_url, ns};
+use kuchikiki::NodeRef;
+
+/// Attributes naming a `<meta>` tag, all used as keys of [`PageMeta::tags`]
+const KEY_ATTRIBUTES: &[&str] = &["name", "property", "itemprop", "http-equiv"];
+
+/// The `<meta>` tags of a page and the common fields derived from them
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageMeta {
+    /// Lowercased `name`/`property`/`itemprop`/`http-equiv` with all their `content`s, in
+    /// order of first appearance
+    pub tags: Vec<(String, Vec<String>)>,
+    /// `<title>`, or else `og:title` or `twitter:title`
+    pub title: Option<String>,
+    /// `description`, or else `og:description` or `twitter:description`
+    pub description: Option<String>,
+    /// `og:image`, or else `og:image:url` or `twitter:image`, as an absolute URL when possible
+    pub image: Option<String>,
+    /// `og:site_name`, or else `application-name`
+    pub site_name: Option<String>,
+    /// `<link rel="icon">`, or else `<link rel="apple-touch-icon">`
+    pub favicon: Option<String>,
+    /// `<link rel="manifest">`
+    pub manifest: Option<String>,
+    pub theme_color: Option<String>,
+}
+
+impl PageMeta {
+    /// First non-empty value of the first of `keys` that has one
+    fn first(&self, keys: &[&str]) -> Option<String> {
+        keys.iter().find_map(|key| {
+            self.tags
+                .iter()
+                .find(|(name, _)| name == key)
+                .and_then(|(_, values)| values.iter().find(|value| !value.is_empty()))
+                .cloned()
+        })
+    }
+}
+
+fn html_elements<'a>(
+    document: &NodeRef,
+    name: &'a html5ever::LocalName,
+) -> impl Iterator<Item = NodeRef> + 'a {
+    document.descendants().filter(move |node| {
+        node.as_element()
+            .is_some_and(|element| element.name.ns == ns!(html) && element.name.local == *name)
+    })
+}
+
+/// `href` of the first `<link>` with one of the `rel` tokens, in order of preference
+fn link_href(document: &NodeRef, rels: &[&str]) -> Option<String> {
+    let links: Vec<(String, String)> = html_elements(document, &local_name!("link"))
+        .filter_map(|link| {
+            let element = link.as_element()?;
+            let attributes = element.attributes.borrow();
+            let href = attributes.get("href")?.trim();
+            (!href.is_empty()).then(|| {
+                (
+                    attributes
+                        .get("rel")
+                        .unwrap_or_default()
+                        .to_ascii_lowercase(),
+                    href.to_string(),
+                )
+            })
+        })
+        .collect();
+    rels.iter().find_map(|rel| {
+        links
+            .iter()
+            .find(|(tokens, _)| tokens.split_whitespace().any(|token| token == *rel))
+            .map(|(_, href)| href.clone())
+    })
+}
+
+/// Collect the `<meta>` tags of a document along with its title, description, image,
+/// site name, favicon, manifest and theme color
+///
+/// URLs are resolved against the document's `<base>` when it has one.
+pub fn html_meta(html: &str) -> PageMeta {
+    let document = parse_document(html, &QueryOptions::default());
+    let base = detect_base(&document);
+    let mut meta = PageMeta::default();
+
+    for node in html_elements(&document, &local_name!("meta")) {
+        let Some(element) = node.as_element() else {
+            continue;
+        };
+        let attributes = element.attributes.borrow();
+        let Some(content) = attributes.get("content") else {
+            continue;
+        };
+        let content = content.trim().to_string();
+        let mut keys: Vec<String> = Vec::new();
+        for key in KEY_ATTRIBUTES
+            .iter()
+            .filter_map(|name| attributes.get(*name))
+        {
+            let key = key.trim().to_ascii_lowercase();
+            if !key.is_empty() && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        for key in keys {
+            match meta.tags.iter_mut().find(|(name, _)| *name == key) {
+                Some((_, values)) => values.push(content.clone()),
+                None => meta.tags.push((key, vec![content.clone()])),
+            }
+        }
+    }
+
+    meta.title = html_elements(&document, &local_name!("title"))
+        .map(|title| collapse_whitespace(&title.text_contents()))
+        .find(|title| !title.is_empty())
+        .or_else(|| meta.first(&["og:title", "twitter:title"]));
+    meta.description = meta.first(&["description", "og:description", "twitter:description"]);
+    meta.image = meta
+        .first(&[
+            "og:image",
+            "og:image:url",
+            "og:image:secure_url",
+            "twitter:image",
+        ])
+        .map(|image| resolve_url(base.as_ref(), &image));
+    meta.site_name = meta.first(&["og:site_name", "application-name"]);
+    meta.favicon = link_href(&document, &["icon", "apple-touch-icon"])
+        .map(|href| resolve_url(base.as_ref(), &href));
+    meta.manifest =
+        link_href(&document, &["manifest"]).map(|href| resolve_url(base.as_ref(), &href));
+    meta.theme_color = meta.first(&["theme-color"]);
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_meta_tags() {
+        let meta = html_meta(
+            r#"<head>
+                 <meta charset="utf-8">
+                 <meta http-equiv="Content-Type" content="text/html">
+                 <meta property="og:title" content="Rust Developer">
+                 <meta property="og:image" content="https://cdn.example/a.png">
+                 <meta property="og:image" content="https://cdn.example/b.png">
+                 <meta name="DC.Creator" content=" Acme ">
+                 <meta itemprop="datePosted" content="2024-05-01">
+               </head>"#,
+        );
+        assert_eq!(
+            meta.tags,
+            vec![
+                ("content-type".to_string(), strings(&["text/html"])),
+                ("og:title".to_string(), strings(&["Rust Developer"])),
+                (
+                    "og:image".to_string(),
+                    strings(&["https://cdn.example/a.png", "https://cdn.example/b.png"])
+                ),
+                ("dc.creator".to_string(), strings(&["Acme"])),
+                ("dateposted".to_string(), strings(&["2024-05-01"])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_common_fields() {
+        let meta = html_meta(
+            r##"<head>
+                 <base href="https://jobs.example/en/">
+                 <title> Jobs  at Acme </title>
+                 <meta property="og:description" content="">
+                 <meta name="twitter:description" content="Join us">
+                 <meta name="twitter:image" content="/card.png">
+                 <meta name="application-name" content="Acme Jobs">
+                 <meta name="theme-color" content="#ff0000">
+                 <link rel="apple-touch-icon" href="/touch.png">
+                 <link rel="shortcut icon" href="favicon.ico">
+                 <link rel="manifest" href="/site.webmanifest">
+               </head><body><svg><title>Logo</title></svg></body>"##,
+        );
+        assert_eq!(meta.title.as_deref(), Some("Jobs at Acme"));
+        assert_eq!(meta.description.as_deref(), Some("Join us"));
+        assert_eq!(meta.image.as_deref(), Some("https://jobs.example/card.png"));
+        assert_eq!(meta.site_name.as_deref(), Some("Acme Jobs"));
+        assert_eq!(
+            meta.favicon.as_deref(),
+            Some("https://jobs.example/en/favicon.ico")
+        );
+        assert_eq!(
+            meta.manifest.as_deref(),
+            Some("https://jobs.example/site.webmanifest")
+        );
+        assert_eq!(meta.theme_color.as_deref(), Some("#ff0000"));
+    }
+
+    #[test]
+    fn test_fallbacks_and_missing_fields() {
+        let meta = html_meta(r#"<meta property="og:title" content="From OG">"#);
+        assert_eq!(meta.title.as_deref(), Some("From OG"));
+        assert_eq!(meta.description, None);
+        assert_eq!(meta.favicon, None);
+        assert_eq!(html_meta("<p>No head</p>"), PageMeta::default());
+    }
+}
@@ -0,0 +1,300 @@
+use crate::link::{detect_base, resolve_url};
+use crate::{attribute, collapse_whitespace, name_of, parse_document, QueryOptions};
+use kuchikiki::{Node, NodeRef};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use url::Url;
+
+/// Vocabulary prefixes compacted to terms under the `https://schema.org` context
+pub(crate) const SCHEMA_ORG: &[&str] = &["https://schema.org/", "http://schema.org/"];
+
+/// Elements whose property value is the URL in their `src` attribute
+const SRC_ELEMENTS: &[&str] = &[
+    "audio", "embed", "iframe", "img", "source", "track", "video",
+];
+
+fn has_attr(node: &NodeRef, name: &str) -> bool {
+    node.as_element()
+        .is_some_and(|element| element.attributes.borrow().contains(name))
+}
+
+fn node_key(node: &NodeRef) -> *const Node {
+    &*node.0
+}
+
+/// Add a property value to a JSON-LD object, turning repeated properties into arrays
+pub(crate) fn add_property(object: &mut Map<String, Value>, name: &str, value: Value) {
+    match object.get_mut(name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            object.insert(name.to_string(), value);
+        }
+    }
+}
+
+struct Document {
+    root: NodeRef,
+    base: Option<Url>,
+    /// Position of each node in tree order
+    order: HashMap<*const Node, usize>,
+}
+
+impl Document {
+    fn new(root: NodeRef) -> Self {
+        let order = root
+            .inclusive_descendants()
+            .enumerate()
+            .map(|(i, node)| (node_key(&node), i))
+            .collect();
+        Document {
+            base: detect_base(&root),
+            root,
+            order,
+        }
+    }
+
+    fn element_by_id(&self, id: &str) -> Option<NodeRef> {
+        self.root
+            .descendants()
+            .find(|node| attribute(node, "id").as_deref() == Some(id))
+    }
+
+    fn url(&self, node: &NodeRef, name: &str) -> String {
+        attribute(node, name)
+            .map(|href| resolve_url(self.base.as_ref(), &href))
+            .unwrap_or_default()
+    }
+
+    /// Elements holding the properties of `item` in tree order, following the WHATWG
+    /// "crawl the properties" steps including `itemref`
+    fn properties(&self, item: &NodeRef) -> Vec<NodeRef> {
+        let mut pending: Vec<NodeRef> = item.children().collect();
+        if let Some(refs) = attribute(item, "itemref") {
+            pending.extend(
+                refs.split_whitespace()
+                    .filter_map(|id| self.element_by_id(id)),
+            );
+        }
+
+        let mut memory = vec![item.clone()];
+        let mut results = Vec::new();
+        while let Some(current) = pending.pop() {
+            if current.as_element().is_none() || memory.contains(&current) {
+                continue;
+            }
+            memory.push(current.clone());
+            if !has_attr(&current, "itemscope") {
+                pending.extend(current.children());
+            }
+            if has_attr(&current, "itemprop") {
+                results.push(current);
+            }
+        }
+
+        results.sort_by_key(|node| self.order.get(&node_key(node)).copied());
+        results
+    }
+
+    fn property_value(&self, node: &NodeRef, stack: &mut Vec<NodeRef>, in_schema: bool) -> Value {
+        if has_attr(node, "itemscope") {
+            // An item containing itself through itemref has no finite value
+            if stack.contains(node) {
+                return Value::Null;
+            }
+            return self.item(node, stack, in_schema);
+        }
+
+        let name = name_of(node);
+        // `content` overrides the element's own value on any element, as schema.org
+        // markup often puts a machine-readable value next to the visible text
+        let value = match attribute(node, "content") {
+            Some(content) => content,
+            None => match name.as_str() {
+                name if SRC_ELEMENTS.contains(&name) => self.url(node, "src"),
+                "a" | "area" | "link" => self.url(node, "href"),
+                "object" => self.url(node, "data"),
+                "data" | "meter" => attribute(node, "value").unwrap_or_default(),
+                "time" => attribute(node, "datetime").unwrap_or_else(|| node.text_contents()),
+                _ => node.text_contents(),
+            },
+        };
+        Value::String(collapse_whitespace(&value))
+    }
+
+    /// JSON-LD object of an item; `in_schema` tells whether a schema.org context is
+    /// already in effect
+    fn item(&self, item: &NodeRef, stack: &mut Vec<NodeRef>, in_schema: bool) -> Value {
+        let mut object = Map::new();
+
+        let types: Vec<String> = attribute(item, "itemtype")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        let is_schema = types
+            .first()
+            .is_some_and(|first| SCHEMA_ORG.iter().any(|prefix| first.starts_with(prefix)));
+        if is_schema && !in_schema {
+            object.insert("@context".into(), "https://schema.org".into());
+        }
+        let types: Vec<Value> = types
+            .iter()
+            .map(|item_type| {
+                let compact = SCHEMA_ORG
+                    .iter()
+                    .find_map(|prefix| item_type.strip_prefix(prefix))
+                    .filter(|_| is_schema);
+                Value::String(compact.unwrap_or(item_type).to_string())
+            })
+            .collect();
+        match types.len() {
+            0 => {}
+            1 => {
+                object.insert("@type".into(), types[0].clone());
+            }
+            _ => {
+                object.insert("@type".into(), Value::Array(types));
+            }
+        }
+        if has_attr(item, "itemid") {
+            object.insert("@id".into(), self.url(item, "itemid").into());
+        }
+
+        stack.push(item.clone());
+        for property in self.properties(item) {
+            let value = self.property_value(&property, stack, in_schema || is_schema);
+            if value.is_null() {
+                continue;
+            }
+            let names = attribute(&property, "itemprop").unwrap_or_default();
+            for name in names.split_whitespace() {
+                add_property(&mut object, name, value.clone());
+            }
+        }
+        stack.pop();
+
+        Value::Object(object)
+    }
+}
+
+/// Extract the microdata items of a document as JSON-LD shaped objects
+///
+/// Top-level items are elements with `itemscope` and no `itemprop`. `itemtype` becomes
+/// `@type`, compacted under an `https://schema.org` `@context` for schema.org types, and
+/// `itemid` becomes `@id`. Properties hold text, or for URL elements (`a`, `img`, ...) the
+/// URL resolved against `<base>`. Properties found more than once become arrays.
+pub fn microdata_items(html: &str) -> Vec<Value> {
+    let document = Document::new(parse_document(html, &QueryOptions::default()));
+    document
+        .root
+        .descendants()
+        .filter(|node| has_attr(node, "itemscope") && !has_attr(node, "itemprop"))
+        .map(|item| document.item(&item, &mut Vec::new(), false))
+        .collect()
+}
+
+/// [`microdata_items`] as a JSON array
+pub fn html_microdata(html: &str) -> String {
+    Value::Array(microdata_items(html)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn microdata(html: &str) -> Value {
+        serde_json::from_str(&html_microdata(html)).unwrap()
+    }
+
+    #[test]
+    fn test_nested_items() {
+        let value = microdata(
+            r#"<div itemscope itemtype="https://schema.org/JobPosting">
+                 <h1 itemprop="title">Rust  Developer</h1>
+                 <meta itemprop="employmentType" content="FULL_TIME">
+                 <time itemprop="datePosted" datetime="2024-05-01">May 1</time>
+                 <div itemprop="hiringOrganization" itemscope itemtype="http://schema.org/Organization">
+                   <span itemprop="name">Acme</span>
+                 </div>
+                 <data itemprop="baseSalary" value="60000">60k</data>
+               </div>"#,
+        );
+        assert_eq!(
+            value,
+            json!([{
+                "@context": "https://schema.org",
+                "@type": "JobPosting",
+                "title": "Rust Developer",
+                "employmentType": "FULL_TIME",
+                "datePosted": "2024-05-01",
+                "hiringOrganization": {"@type": "Organization", "name": "Acme"},
+                "baseSalary": "60000"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_itemref_and_repeated_properties() {
+        let value = microdata(
+            r#"<div itemscope itemref="extra"><span itemprop="skill">Rust</span><span itemprop="skill">SQL</span></div>
+               <p id="extra"><span itemprop="skill">Go</span><span itemprop="note other">x</span></p>"#,
+        );
+        assert_eq!(
+            value,
+            json!([{"skill": ["Rust", "SQL", "Go"], "note": "x", "other": "x"}])
+        );
+    }
+
+    #[test]
+    fn test_urls_resolved_against_base() {
+        let value = microdata(
+            r#"<base href="https://jobs.example/a/">
+               <div itemscope itemtype="https://example.org/Thing" itemid="/t/1">
+                 <a itemprop="url" href="apply">Apply</a><img itemprop="logo" src="/logo.png">
+               </div>"#,
+        );
+        assert_eq!(
+            value,
+            json!([{
+                "@type": "https://example.org/Thing",
+                "@id": "https://jobs.example/t/1",
+                "url": "https://jobs.example/a/apply",
+                "logo": "https://jobs.example/logo.png"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_content_attribute_on_any_element() {
+        let value = microdata(
+            r#"<div itemscope itemtype="https://schema.org/Offer">
+                 <span itemprop="price" content="1199">Call for price</span>
+                 <a itemprop="url" href="/buy" content="https://shop.example/chair">Buy</a>
+                 <span itemprop="priceCurrency">SEK</span>
+               </div>"#,
+        );
+        assert_eq!(
+            value,
+            json!([{
+                "@context": "https://schema.org",
+                "@type": "Offer",
+                "price": "1199",
+                "url": "https://shop.example/chair",
+                "priceCurrency": "SEK"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_itemref_cycle() {
+        let value = microdata(
+            r#"<div itemscope itemref="x"></div>
+               <div id="x" itemprop="first" itemscope itemref="y"></div>
+               <div id="y" itemprop="second" itemscope itemref="x"></div>"#,
+        );
+        assert_eq!(value, json!([{"first": {"second": {}}}]));
+        assert_eq!(microdata("<p>No items</p>"), json!([]));
+    }
+}
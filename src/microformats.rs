@@ -0,0 +1,584 @@
+use crate::link::{absolutize_links, detect_base, resolve_url};
+use crate::{attribute, collapse_whitespace, name_of, parse_document, QueryOptions};
+use kuchikiki::iter::NodeEdge;
+use kuchikiki::NodeRef;
+use serde_json::{json, Map, Value};
+use url::Url;
+
+/// Property prefixes, `p-` plain text, `u-` URLs, `dt-` dates and `e-` embedded markup
+const PROPERTY_PREFIXES: &[&str] = &["p-", "u-", "dt-", "e-"];
+
+/// Whether `name` is a valid mf2 name: an optional vendor prefix and lowercase words,
+/// as in `card`, `x-custom` or `mf2-example`
+fn is_mf2_name(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('-').collect();
+    let is_word = |part: &&str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_lowercase());
+    match parts.as_slice() {
+        [] => false,
+        [word] => is_word(word),
+        [vendor, words @ ..] => {
+            !vendor.is_empty()
+                && vendor
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+                && words.iter().all(is_word)
+        }
+    }
+}
+
+fn classes(node: &NodeRef) -> Vec<String> {
+    attribute(node, "class")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Root class names (`h-card`), sorted and without duplicates
+fn root_classes(node: &NodeRef) -> Vec<String> {
+    let mut roots: Vec<String> = classes(node)
+        .into_iter()
+        .filter(|class| class.strip_prefix("h-").is_some_and(is_mf2_name))
+        .collect();
+    roots.sort();
+    roots.dedup();
+    roots
+}
+
+/// Property classes as `(prefix, name)`, e.g. `("dt-", "published")`
+fn property_classes(node: &NodeRef) -> Vec<(&'static str, String)> {
+    let mut properties = Vec::new();
+    for class in classes(node) {
+        for prefix in PROPERTY_PREFIXES {
+            if let Some(name) = class.strip_prefix(prefix).filter(|name| is_mf2_name(name)) {
+                let property = (*prefix, name.to_string());
+                if !properties.contains(&property) {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    properties
+}
+
+/// Text of a node with `<img>`s replaced by their `alt`, leaving out scripts and styles
+fn text_with_alt(node: &NodeRef) -> String {
+    let mut text = String::new();
+    let mut skipped = 0;
+    for edge in node.traverse() {
+        match edge {
+            NodeEdge::Start(child) => {
+                let name = name_of(&child);
+                if matches!(name.as_str(), "script" | "style" | "template") {
+                    skipped += 1;
+                } else if skipped > 0 {
+                    continue;
+                } else if let Some(chunk) = child.as_text() {
+                    text.push_str(&chunk.borrow());
+                } else if name == "img" {
+                    if let Some(alt) = attribute(&child, "alt") {
+                        text.push_str(&format!(" {} ", alt));
+                    }
+                }
+            }
+            NodeEdge::End(child) => {
+                if matches!(name_of(&child).as_str(), "script" | "style" | "template") {
+                    skipped -= 1;
+                }
+            }
+        }
+    }
+    collapse_whitespace(&text)
+}
+
+/// The only element child of a node, if it has exactly one
+fn only_child(node: &NodeRef) -> Option<NodeRef> {
+    let mut elements = node.children().filter(|child| child.as_element().is_some());
+    let first = elements.next()?;
+    elements.next().is_none().then_some(first)
+}
+
+/// Only child that is of one of `names` and not a microformat itself
+fn only_child_of(node: &NodeRef, names: &[&str]) -> Option<NodeRef> {
+    only_child(node)
+        .filter(|child| names.contains(&name_of(child).as_str()) && root_classes(child).is_empty())
+}
+
+/// Value class pattern: the values of descendants with class `value` or `value-title`
+fn value_class(node: &NodeRef) -> Option<Vec<String>> {
+    let values: Vec<String> = node
+        .descendants()
+        .filter(|child| {
+            let classes = classes(child);
+            classes
+                .iter()
+                .any(|class| class == "value" || class == "value-title")
+        })
+        .filter(|child| {
+            // Values of nested properties belong to them
+            child
+                .ancestors()
+                .take_while(|ancestor| ancestor != node)
+                .all(|ancestor| property_classes(&ancestor).is_empty())
+        })
+        .map(|child| {
+            if classes(&child).iter().any(|class| class == "value-title") {
+                return attribute(&child, "title").unwrap_or_default();
+            }
+            match name_of(&child).as_str() {
+                "img" | "area" => attribute(&child, "alt"),
+                "data" => attribute(&child, "value"),
+                "abbr" => attribute(&child, "title"),
+                "del" | "ins" | "time" => attribute(&child, "datetime"),
+                _ => None,
+            }
+            .unwrap_or_else(|| child.text_contents())
+        })
+        .collect();
+    (!values.is_empty()).then_some(values)
+}
+
+struct Parser {
+    base: Option<Url>,
+}
+
+impl Parser {
+    fn url(&self, href: &str) -> String {
+        resolve_url(self.base.as_ref(), href)
+    }
+
+    fn plain_value(&self, node: &NodeRef) -> String {
+        if let Some(values) = value_class(node) {
+            return values.concat();
+        }
+        match name_of(node).as_str() {
+            "abbr" | "link" => attribute(node, "title"),
+            "data" | "input" => attribute(node, "value"),
+            "img" | "area" => attribute(node, "alt"),
+            _ => None,
+        }
+        .unwrap_or_else(|| text_with_alt(node))
+    }
+
+    fn url_value(&self, node: &NodeRef) -> Value {
+        let name = name_of(node);
+        if name == "img" {
+            if let Some(src) = attribute(node, "src") {
+                return match attribute(node, "alt") {
+                    Some(alt) => json!({"value": self.url(&src), "alt": alt}),
+                    None => Value::String(self.url(&src)),
+                };
+            }
+        }
+        let url = match name.as_str() {
+            "a" | "area" | "link" => attribute(node, "href"),
+            "audio" | "iframe" | "source" => attribute(node, "src"),
+            "video" => attribute(node, "src").or_else(|| attribute(node, "poster")),
+            "object" => attribute(node, "data"),
+            _ => None,
+        };
+        if let Some(url) = url {
+            return Value::String(self.url(&url));
+        }
+        let value = value_class(node)
+            .map(|values| values.concat())
+            .unwrap_or_else(|| {
+                match name.as_str() {
+                    "abbr" => attribute(node, "title"),
+                    "data" | "input" => attribute(node, "value"),
+                    _ => None,
+                }
+                .unwrap_or_else(|| collapse_whitespace(&node.text_contents()))
+            });
+        Value::String(self.url(&value))
+    }
+
+    fn date_value(&self, node: &NodeRef) -> String {
+        // Separate date and time parts are combined as "2024-06-01 10:00"
+        if let Some(values) = value_class(node) {
+            return collapse_whitespace(&values.join(" "));
+        }
+        match name_of(node).as_str() {
+            "time" | "ins" | "del" => attribute(node, "datetime"),
+            "abbr" => attribute(node, "title"),
+            "data" | "input" => attribute(node, "value"),
+            _ => None,
+        }
+        .unwrap_or_else(|| collapse_whitespace(&node.text_contents()))
+    }
+
+    fn embedded_value(&self, node: &NodeRef) -> Value {
+        let html: String = node.children().map(|child| child.to_string()).collect();
+        let html = match &self.base {
+            Some(base) => absolutize_links(&html, base),
+            None => html,
+        };
+        json!({
+            "html": html.trim(),
+            "value": text_with_alt(node),
+        })
+    }
+
+    fn property_value(&self, prefix: &str, node: &NodeRef) -> Value {
+        match prefix {
+            "p-" => Value::String(self.plain_value(node)),
+            "u-" => self.url_value(node),
+            "dt-" => Value::String(self.date_value(node)),
+            _ => self.embedded_value(node),
+        }
+    }
+
+    /// Parse a root element into an item with `type`, `properties` and `children`
+    fn item(&self, node: &NodeRef) -> Map<String, Value> {
+        let mut properties = Map::new();
+        let mut children = Vec::new();
+        for child in node.children() {
+            self.walk(&child, &mut properties, &mut children);
+        }
+        self.implied_properties(node, &mut properties, children.is_empty());
+
+        let mut item = Map::new();
+        item.insert("type".into(), json!(root_classes(node)));
+        item.insert("properties".into(), Value::Object(properties));
+        if let Some(id) = attribute(node, "id").filter(|id| !id.is_empty()) {
+            item.insert("id".into(), Value::String(id));
+        }
+        if !children.is_empty() {
+            item.insert("children".into(), Value::Array(children));
+        }
+        item
+    }
+
+    /// Implied `name`, `photo` and `url` of an item without explicit ones
+    fn implied_properties(
+        &self,
+        node: &NodeRef,
+        properties: &mut Map<String, Value>,
+        no_children: bool,
+    ) {
+        // Explicit properties of a kind, and nested items, rule out the implied ones
+        let explicit = |prefixes: &[&str]| {
+            node.descendants().any(|child| {
+                child
+                    .ancestors()
+                    .take_while(|ancestor| ancestor != node)
+                    .all(|ancestor| root_classes(&ancestor).is_empty())
+                    && property_classes(&child)
+                        .iter()
+                        .any(|(prefix, _)| prefixes.contains(prefix))
+            })
+        };
+        let has_nested_items = !no_children
+            || properties
+                .values()
+                .flat_map(|values| values.as_array().into_iter().flatten())
+                .any(|value| value.get("type").is_some());
+
+        if !properties.contains_key("name") && !explicit(&["p-", "e-"]) && !has_nested_items {
+            let name = match name_of(node).as_str() {
+                "img" | "area" => attribute(node, "alt"),
+                "abbr" => attribute(node, "title"),
+                _ => None,
+            }
+            .or_else(|| {
+                let child = only_child(node).filter(|child| root_classes(child).is_empty())?;
+                match name_of(&child).as_str() {
+                    "img" | "area" => attribute(&child, "alt"),
+                    "abbr" => attribute(&child, "title"),
+                    _ => None,
+                }
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| text_with_alt(node));
+            properties.insert("name".into(), json!([name]));
+        }
+
+        let no_urls = !explicit(&["u-"]) && !has_nested_items;
+        if !properties.contains_key("photo") && no_urls {
+            let photo = match name_of(node).as_str() {
+                "img" => Some(node.clone()),
+                "object" => Some(node.clone()),
+                _ => only_child_of(node, &["img", "object"]).or_else(|| {
+                    only_child(node).and_then(|child| only_child_of(&child, &["img", "object"]))
+                }),
+            };
+            if let Some(photo) = photo.filter(|photo| {
+                attribute(photo, "src")
+                    .or_else(|| attribute(photo, "data"))
+                    .is_some()
+            }) {
+                properties.insert("photo".into(), json!([self.url_value(&photo)]));
+            }
+        }
+
+        if !properties.contains_key("url") && no_urls {
+            let link = match name_of(node).as_str() {
+                "a" | "area" => Some(node.clone()),
+                _ => only_child_of(node, &["a", "area"]).or_else(|| {
+                    only_child(node).and_then(|child| only_child_of(&child, &["a", "area"]))
+                }),
+            };
+            if let Some(href) = link.and_then(|link| attribute(&link, "href")) {
+                properties.insert("url".into(), json!([self.url(&href)]));
+            }
+        }
+    }
+
+    /// Collect the properties and nested items below an item's element
+    fn walk(&self, node: &NodeRef, properties: &mut Map<String, Value>, children: &mut Vec<Value>) {
+        if node.as_element().is_none() {
+            return;
+        }
+        let property_classes = property_classes(node);
+
+        if !root_classes(node).is_empty() {
+            let item = self.item(node);
+            if property_classes.is_empty() {
+                children.push(Value::Object(item));
+                return;
+            }
+            for (prefix, name) in property_classes {
+                let mut item = item.clone();
+                // The value of a nested item property is its name, url or text
+                let from = match prefix {
+                    "p-" => "name",
+                    "u-" => "url",
+                    _ => "",
+                };
+                let value = item["properties"]
+                    .get(from)
+                    .and_then(|values| values.get(0))
+                    .cloned()
+                    .unwrap_or_else(|| self.property_value(prefix, node));
+                let value = match value {
+                    Value::Object(embedded) if prefix == "e-" => {
+                        item.insert("html".into(), embedded["html"].clone());
+                        embedded["value"].clone()
+                    }
+                    Value::Object(url) => url["value"].clone(),
+                    value => value,
+                };
+                item.insert("value".into(), value);
+                push_property(properties, &name, Value::Object(item));
+            }
+            return;
+        }
+
+        for (prefix, name) in &property_classes {
+            push_property(properties, name, self.property_value(prefix, node));
+        }
+        for child in node.children() {
+            self.walk(&child, properties, children);
+        }
+    }
+
+    fn top_level_items(&self, node: &NodeRef, items: &mut Vec<Value>) {
+        for child in node.children() {
+            if child.as_element().is_none() {
+                continue;
+            }
+            if root_classes(&child).is_empty() {
+                self.top_level_items(&child, items);
+            } else {
+                items.push(Value::Object(self.item(&child)));
+            }
+        }
+    }
+
+    /// `rels` and `rel-urls` from the `rel` attributes of links
+    fn rels(&self, document: &NodeRef) -> (Map<String, Value>, Map<String, Value>) {
+        let mut rels: Map<String, Value> = Map::new();
+        let mut rel_urls: Map<String, Value> = Map::new();
+        for node in document.descendants() {
+            if !matches!(name_of(&node).as_str(), "a" | "area" | "link") {
+                continue;
+            }
+            let (Some(rel), Some(href)) = (attribute(&node, "rel"), attribute(&node, "href"))
+            else {
+                continue;
+            };
+            let url = self.url(&href);
+            let names: Vec<String> = rel.split_whitespace().map(str::to_lowercase).collect();
+            if names.is_empty() {
+                continue;
+            }
+
+            for name in &names {
+                let urls = rels.entry(name.clone()).or_insert_with(|| json!([]));
+                if let Value::Array(urls) = urls {
+                    if !urls.iter().any(|existing| existing == &url) {
+                        urls.push(Value::String(url.clone()));
+                    }
+                }
+            }
+
+            let entry = rel_urls.entry(url).or_insert_with(|| {
+                let mut entry = Map::new();
+                entry.insert("rels".into(), json!([]));
+                for name in ["hreflang", "media", "title", "type"] {
+                    if let Some(value) = attribute(&node, name) {
+                        entry.insert(name.into(), Value::String(value));
+                    }
+                }
+                let text = collapse_whitespace(&node.text_contents());
+                if !text.is_empty() {
+                    entry.insert("text".into(), Value::String(text));
+                }
+                Value::Object(entry)
+            });
+            if let Some(Value::Array(existing)) = entry.get_mut("rels") {
+                for name in names {
+                    if !existing.iter().any(|rel| rel == &name) {
+                        existing.push(Value::String(name));
+                    }
+                }
+            }
+        }
+        (rels, rel_urls)
+    }
+}
+
+fn push_property(properties: &mut Map<String, Value>, name: &str, value: Value) {
+    if let Value::Array(values) = properties
+        .entry(name.to_string())
+        .or_insert_with(|| json!([]))
+    {
+        values.push(value);
+    }
+}
+
+/// Parse microformats2 (`h-card`, `h-entry`, `h-event`, ...) following the mf2 parsing spec
+///
+/// Returns the canonical `{"items", "rels", "rel-urls"}` JSON. URLs are resolved against the
+/// document's `<base>`, or else `base_url`. Classic microformats (`vcard`, `hentry`) are not
+/// parsed.
+pub fn html_microformats(html: &str, base_url: Option<&str>) -> String {
+    let document = parse_document(html, &QueryOptions::default());
+    let parser = Parser {
+        base: detect_base(&document).or_else(|| base_url.and_then(|base| Url::parse(base).ok())),
+    };
+
+    let mut items = Vec::new();
+    parser.top_level_items(&document, &mut items);
+    let (rels, rel_urls) = parser.rels(&document);
+    json!({
+        "items": items,
+        "rels": rels,
+        "rel-urls": rel_urls,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn microformats(html: &str, base_url: Option<&str>) -> Value {
+        serde_json::from_str(&html_microformats(html, base_url)).unwrap()
+    }
+
+    #[test]
+    fn test_mf2_names() {
+        assert!(is_mf2_name("card"));
+        assert!(is_mf2_name("x-custom-thing"));
+        assert!(is_mf2_name("mf2-entry"));
+        assert!(!is_mf2_name("Card"));
+        assert!(!is_mf2_name("card-"));
+        assert!(!is_mf2_name("2"));
+    }
+
+    #[test]
+    fn test_h_card_with_implied_properties() {
+        let value = microformats(
+            r#"<a class="h-card" href="/about"><img src="me.jpg" alt="">Jane Doe</a>"#,
+            Some("https://jane.example/blog/"),
+        );
+        assert_eq!(
+            value["items"],
+            json!([{
+                "type": ["h-card"],
+                "properties": {
+                    "name": ["Jane Doe"],
+                    "photo": [{"value": "https://jane.example/blog/me.jpg", "alt": ""}],
+                    "url": ["https://jane.example/about"]
+                }
+            }])
+        );
+
+        let value = microformats(
+            r#"<div class="h-card"><img src="/me.jpg" alt="Jane"></div>"#,
+            None,
+        );
+        assert_eq!(
+            value["items"][0]["properties"],
+            json!({"name": ["Jane"], "photo": [{"value": "/me.jpg", "alt": "Jane"}]})
+        );
+    }
+
+    #[test]
+    fn test_h_entry_with_nested_items() {
+        let value = microformats(
+            r#"<article class="h-entry" id="post">
+                 <h1 class="p-name">Hiring <b>Rust</b> devs</h1>
+                 <time class="dt-published" datetime="2024-05-01T10:00">May 1</time>
+                 <a class="u-url" href="/jobs/1">Permalink</a>
+                 <div class="p-author h-card"><a class="p-name u-url" href="/">Acme</a></div>
+                 <div class="e-content"><p>Apply <a href="apply">here</a></p></div>
+                 <div class="h-event"><span class="p-name">Meetup</span></div>
+               </article>"#,
+            Some("https://acme.example/"),
+        );
+        assert_eq!(
+            value["items"],
+            json!([{
+                "type": ["h-entry"],
+                "id": "post",
+                "properties": {
+                    "name": ["Hiring Rust devs"],
+                    "published": ["2024-05-01T10:00"],
+                    "url": ["https://acme.example/jobs/1"],
+                    "author": [{
+                        "type": ["h-card"],
+                        "properties": {"name": ["Acme"], "url": ["https://acme.example/"]},
+                        "value": "Acme"
+                    }],
+                    "content": [{
+                        "html": r#"<p>Apply <a href="https://acme.example/apply">here</a></p>"#,
+                        "value": "Apply here"
+                    }]
+                },
+                "children": [{"type": ["h-event"], "properties": {"name": ["Meetup"]}}]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_value_class_pattern() {
+        let value = microformats(
+            r#"<div class="h-event"><span class="p-name">Fair</span>
+                 <span class="dt-start"><span class="value">2024-06-01</span> at <span class="value">10:00</span></span></div>"#,
+            None,
+        );
+        assert_eq!(
+            value["items"][0]["properties"]["start"],
+            json!(["2024-06-01 10:00"])
+        );
+    }
+
+    #[test]
+    fn test_rels() {
+        let value = microformats(
+            r#"<link rel="me" href="https://github.com/jane"><a rel="me nofollow" href="/cv" hreflang="en">CV</a>"#,
+            Some("https://jane.example/"),
+        );
+        assert_eq!(
+            value["rels"],
+            json!({"me": ["https://github.com/jane", "https://jane.example/cv"], "nofollow": ["https://jane.example/cv"]})
+        );
+        assert_eq!(
+            value["rel-urls"]["https://jane.example/cv"],
+            json!({"rels": ["me", "nofollow"], "hreflang": "en", "text": "CV"})
+        );
+        assert_eq!(value["items"], json!([]));
+    }
+}
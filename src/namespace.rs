@@ -0,0 +1,160 @@
+use html5ever::{namespace_url, ns, Namespace};
+use kuchikiki::{Attributes, NodeRef};
+
+/// Namespace prefixes that can be used in selectors and attribute names without declaring them
+const WELL_KNOWN_PREFIXES: &[&str] = &[
+    "html", "xhtml", "svg", "math", "mathml", "xlink", "xml", "xmlns",
+];
+
+/// Resolve a well-known namespace prefix such as `svg` or `xlink`
+pub fn well_known_namespace(prefix: &str) -> Option<Namespace> {
+    match prefix.to_ascii_lowercase().as_str() {
+        "html" | "xhtml" => Some(ns!(html)),
+        "svg" => Some(ns!(svg)),
+        "math" | "mathml" => Some(ns!(mathml)),
+        "xlink" => Some(ns!(xlink)),
+        "xml" => Some(ns!(xml)),
+        "xmlns" => Some(ns!(xmlns)),
+        _ => None,
+    }
+}
+
+/// Namespace prefixes available to selectors run against `root`
+///
+/// Starts with the well-known prefixes and adds every `xmlns:prefix="uri"` declaration
+/// found in the document, later declarations taking precedence. The XML parser resolves
+/// and drops these declarations, so prefixed element names are used as well.
+pub fn namespace_prefixes(root: &NodeRef) -> Vec<(String, Namespace)> {
+    let mut prefixes: Vec<(String, Namespace)> = WELL_KNOWN_PREFIXES
+        .iter()
+        .filter_map(|prefix| well_known_namespace(prefix).map(|ns| (prefix.to_string(), ns)))
+        .collect();
+
+    for node in root.inclusive_descendants() {
+        let Some(element) = node.as_element() else {
+            continue;
+        };
+        if let Some(prefix) = &element.name.prefix {
+            if !prefixes.iter().any(|(existing, _)| **existing == **prefix) {
+                prefixes.push((prefix.to_string(), element.name.ns.clone()));
+            }
+        }
+        for (name, attr) in element.attributes.borrow().map.iter() {
+            let declared = if name.ns == ns!(xmlns) {
+                Some(name.local.to_string())
+            } else {
+                name.local
+                    .strip_prefix("xmlns:")
+                    .filter(|_| name.ns == ns!())
+                    .map(str::to_string)
+            };
+            // A default namespace (`xmlns="..."`) doesn't declare a prefix
+            if let Some(prefix) = declared.filter(|prefix| prefix != "xmlns") {
+                prefixes.retain(|(existing, _)| *existing != prefix);
+                prefixes.push((prefix, Namespace::from(attr.value.as_str())));
+            }
+        }
+    }
+
+    prefixes
+}
+
+/// Look up an attribute value by name
+///
+/// Accepts `prefix:local` (or `prefix|local`) names for namespaced attributes such as
+/// `xlink:href`. Unprefixed names first match attributes in no namespace, then any
+/// namespace (so `href` finds `xlink:href`), then ignore ASCII case (so `viewbox` finds
+/// the camel-cased SVG `viewBox`).
+pub fn attribute_value(attrs: &Attributes, name: &str) -> Option<String> {
+    if let Some(value) = attrs.get(name) {
+        return Some(value.to_string());
+    }
+
+    if let Some((prefix, local)) = name.split_once([':', '|']) {
+        let namespace = well_known_namespace(prefix);
+        return attrs
+            .map
+            .iter()
+            .find(|(expanded, attr)| {
+                *expanded.local == *local
+                    && (attr.prefix.as_deref() == Some(prefix)
+                        || namespace.as_ref() == Some(&expanded.ns))
+            })
+            .map(|(_, attr)| attr.value.clone());
+    }
+
+    attrs
+        .map
+        .iter()
+        .find(|(expanded, _)| *expanded.local == *name)
+        .or_else(|| {
+            attrs
+                .map
+                .iter()
+                .find(|(expanded, _)| expanded.local.as_ref().eq_ignore_ascii_case(name))
+        })
+        .map(|(_, attr)| attr.value.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    const SVG_HTML: &str = r##"<svg viewBox="0 0 10 10" xmlns:xlink="http://www.w3.org/1999/xlink"><use xlink:href="#logo"/></svg>"##;
+
+    fn attrs_of(html: &str, selector: &str, name: &str) -> Option<String> {
+        let doc = kuchikiki::parse_html().one(html);
+        let node = doc.select_first(selector).unwrap();
+        let attrs = node.attributes.borrow();
+        attribute_value(&attrs, name)
+    }
+
+    #[test]
+    fn test_prefixed_attribute() {
+        assert_eq!(
+            attrs_of(SVG_HTML, "use", "xlink:href"),
+            Some("#logo".to_string())
+        );
+        assert_eq!(
+            attrs_of(SVG_HTML, "use", "xlink|href"),
+            Some("#logo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_unprefixed_name_matches_any_namespace() {
+        assert_eq!(attrs_of(SVG_HTML, "use", "href"), Some("#logo".to_string()));
+    }
+
+    #[test]
+    fn test_camel_cased_svg_attribute() {
+        assert_eq!(
+            attrs_of(SVG_HTML, "svg", "viewBox"),
+            Some("0 0 10 10".to_string())
+        );
+        assert_eq!(
+            attrs_of(SVG_HTML, "svg", "viewbox"),
+            Some("0 0 10 10".to_string())
+        );
+    }
+
+    #[test]
+    fn test_missing_attribute() {
+        assert_eq!(attrs_of(SVG_HTML, "use", "xml:lang"), None);
+        assert_eq!(attrs_of(SVG_HTML, "use", "src"), None);
+    }
+
+    #[test]
+    fn test_declared_prefixes() {
+        let doc = kuchikiki::parse_html()
+            .one(r#"<html xmlns:og="http://ogp.me/ns#"><body></body></html>"#);
+        let prefixes = namespace_prefixes(&doc);
+        assert!(prefixes
+            .iter()
+            .any(|(prefix, ns)| prefix == "og" && &**ns == "http://ogp.me/ns#"));
+        assert!(prefixes
+            .iter()
+            .any(|(prefix, ns)| prefix == "svg" && *ns == ns!(svg)));
+    }
+}
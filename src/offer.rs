@@ -0,0 +1,429 @@
+use crate::job_posting::{text, values};
+use crate::jsonld::{has_type, jsonld_nodes};
+use crate::meta::html_meta;
+use crate::microdata::{microdata_items, SCHEMA_ORG};
+use crate::salary::{currency, parse_amount};
+use crate::{parse_document, QueryOptions};
+use serde_json::Value;
+
+/// schema.org ItemAvailability values
+const AVAILABILITY: &[&str] = &[
+    "BackOrder",
+    "Discontinued",
+    "InStock",
+    "InStoreOnly",
+    "LimitedAvailability",
+    "OnlineOnly",
+    "OutOfStock",
+    "PreOrder",
+    "PreSale",
+    "SoldOut",
+];
+
+/// Properties holding a GTIN, most specific first
+const GTIN_PROPERTIES: &[&str] = &["gtin13", "gtin14", "gtin12", "gtin8", "gtin", "isbn"];
+
+/// OpenGraph product tags of each field
+const OPENGRAPH_TAGS: &[(&str, &[&str])] = &[
+    ("price", &["product:price:amount", "og:price:amount"]),
+    ("currency", &["product:price:currency", "og:price:currency"]),
+    ("availability", &["product:availability", "og:availability"]),
+    ("sku", &["product:retailer_item_id", "product:sku"]),
+    (
+        "gtin",
+        &["product:gtin", "product:ean", "product:upc", "product:isbn"],
+    ),
+];
+
+/// A product offer merged from JSON-LD, microdata, OpenGraph and `itemprop` markup
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Offer {
+    pub price: Option<f64>,
+    /// `lowPrice` of an AggregateOffer
+    pub low_price: Option<f64>,
+    /// `highPrice` of an AggregateOffer
+    pub high_price: Option<f64>,
+    pub currency: Option<String>,
+    /// schema.org ItemAvailability name such as `InStock`
+    pub availability: Option<String>,
+    pub sku: Option<String>,
+    pub gtin: Option<String>,
+    pub seller: Option<String>,
+    /// Source of each field that is set: `json-ld`, `microdata`, `opengraph` or `itemprop`
+    pub sources: Vec<(String, String)>,
+}
+
+fn fill<T>(
+    slot: &mut Option<T>,
+    value: Option<T>,
+    field: &str,
+    source: &str,
+    sources: &mut Vec<(String, String)>,
+) {
+    if slot.is_none() && value.is_some() {
+        *slot = value;
+        sources.push((field.to_string(), source.to_string()));
+    }
+}
+
+impl Offer {
+    /// Set the fields still missing from `other`, recording `source` as where they came from
+    fn merge(&mut self, other: Offer, source: &str) {
+        let sources = &mut self.sources;
+        fill(&mut self.price, other.price, "price", source, sources);
+        fill(
+            &mut self.low_price,
+            other.low_price,
+            "low_price",
+            source,
+            sources,
+        );
+        fill(
+            &mut self.high_price,
+            other.high_price,
+            "high_price",
+            source,
+            sources,
+        );
+        fill(
+            &mut self.currency,
+            other.currency,
+            "currency",
+            source,
+            sources,
+        );
+        fill(
+            &mut self.availability,
+            other.availability,
+            "availability",
+            source,
+            sources,
+        );
+        fill(&mut self.sku, other.sku, "sku", source, sources);
+        fill(&mut self.gtin, other.gtin, "gtin", source, sources);
+        fill(&mut self.seller, other.seller, "seller", source, sources);
+    }
+
+    fn from_source(other: Offer, source: &str) -> Offer {
+        let mut offer = Offer::default();
+        offer.merge(other, source);
+        offer
+    }
+}
+
+/// Price of a text, `19.99` as written by structured data, else in any locale's format
+fn price_of_text(text: &str) -> Option<f64> {
+    text.trim().parse().ok().or_else(|| parse_amount(text))
+}
+
+fn price(value: Option<&Value>) -> Option<f64> {
+    match values(value).into_iter().next()? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => price_of_text(text),
+        Value::Object(object) => price(object.get("@value")),
+        _ => None,
+    }
+}
+
+/// ItemAvailability name of a schema.org URL or of OpenGraph's `instock`, `oos`, ...
+fn availability(text: &str) -> Option<String> {
+    let name = SCHEMA_ORG
+        .iter()
+        .chain(&["schema:"])
+        .find_map(|prefix| text.trim().strip_prefix(prefix))
+        .unwrap_or(text.trim());
+    let key: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if key.is_empty() {
+        return None;
+    }
+    let known = match key.as_str() {
+        "oos" => Some("OutOfStock"),
+        "availablefororder" => Some("PreOrder"),
+        key => AVAILABILITY
+            .iter()
+            .find(|value| value.eq_ignore_ascii_case(key))
+            .copied(),
+    };
+    Some(known.map_or_else(|| name.to_string(), str::to_string))
+}
+
+fn gtin(node: &Value) -> Option<String> {
+    GTIN_PROPERTIES
+        .iter()
+        .find_map(|property| text(node.get(*property)))
+}
+
+/// Normalize an Offer or AggregateOffer node, taking its SKU and GTIN from the product
+/// when the offer has none
+fn structured_offer(node: &Value, product: Option<&Value>) -> Offer {
+    let specification = values(node.get("priceSpecification")).into_iter().next();
+    let product_text = |property: &str| product.and_then(|product| text(product.get(property)));
+    Offer {
+        price: price(node.get("price"))
+            .or_else(|| specification.and_then(|spec| price(spec.get("price")))),
+        low_price: price(node.get("lowPrice")),
+        high_price: price(node.get("highPrice")),
+        currency: text(node.get("priceCurrency"))
+            .or_else(|| specification.and_then(|spec| text(spec.get("priceCurrency")))),
+        availability: text(node.get("availability")).and_then(|text| availability(&text)),
+        sku: text(node.get("sku")).or_else(|| product_text("sku")),
+        gtin: gtin(node).or_else(|| product.and_then(gtin)),
+        seller: text(node.get("seller")),
+        sources: Vec::new(),
+    }
+}
+
+/// Offers of JSON-LD or microdata nodes in document order, an AggregateOffer counting as one
+fn collect_offers(value: &Value, product: Option<&Value>, offers: &mut Vec<Offer>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_offers(value, product, offers);
+            }
+        }
+        Value::Object(object) => {
+            if has_type(value, "Offer") || has_type(value, "AggregateOffer") {
+                offers.push(structured_offer(value, product));
+                return;
+            }
+            let product = if has_type(value, "Product") {
+                Some(value)
+            } else {
+                product
+            };
+            for (key, value) in object {
+                // A product's offers may leave out their type
+                match value {
+                    Value::Object(offer) if key == "offers" && !offer.contains_key("@type") => {
+                        offers.push(structured_offer(value, product))
+                    }
+                    value => collect_offers(value, product, offers),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn structured_offers(items: &[Value]) -> Vec<Offer> {
+    let mut offers = Vec::new();
+    for item in items {
+        collect_offers(item, None, &mut offers);
+    }
+    offers
+}
+
+fn opengraph_offer(html: &str) -> Offer {
+    let meta = html_meta(html);
+    let tag = |field: &str| {
+        OPENGRAPH_TAGS
+            .iter()
+            .filter(|(name, _)| *name == field)
+            .flat_map(|(_, tags)| tags.iter())
+            .find_map(|tag| {
+                meta.tags
+                    .iter()
+                    .find(|(name, _)| name == tag)
+                    .and_then(|(_, values)| values.iter().find(|value| !value.is_empty()))
+                    .cloned()
+            })
+    };
+    Offer {
+        price: tag("price").and_then(|text| price_of_text(&text)),
+        currency: tag("currency"),
+        availability: tag("availability").and_then(|text| availability(&text)),
+        sku: tag("sku"),
+        gtin: tag("gtin"),
+        ..Default::default()
+    }
+}
+
+/// Offer fields of `itemprop` markup anywhere in the page, inside an item or not
+fn itemprop_offer(html: &str) -> Offer {
+    let document = parse_document(html, &QueryOptions::default());
+    let mut offer = Offer::default();
+    // "$19.99" names its currency, unless a `priceCurrency` does
+    let mut implied_currency = None;
+    for node in document.descendants() {
+        let Some(element) = node.as_element() else {
+            continue;
+        };
+        let attributes = element.attributes.borrow();
+        let Some(names) = attributes.get("itemprop") else {
+            continue;
+        };
+        let value = ["content", "value", "href"]
+            .iter()
+            .find_map(|name| attributes.get(*name))
+            .map(str::to_string)
+            .unwrap_or_else(|| node.text_contents());
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        for name in names.split_whitespace() {
+            match name.to_ascii_lowercase().as_str() {
+                "price" => {
+                    offer.price = offer.price.or_else(|| price_of_text(value));
+                    implied_currency = implied_currency.or_else(|| currency(value));
+                }
+                "lowprice" => offer.low_price = offer.low_price.or_else(|| price_of_text(value)),
+                "highprice" => offer.high_price = offer.high_price.or_else(|| price_of_text(value)),
+                "pricecurrency" => {
+                    offer.currency = offer.currency.take().or_else(|| Some(value.to_string()))
+                }
+                "availability" => {
+                    offer.availability = offer.availability.take().or_else(|| availability(value))
+                }
+                "sku" => offer.sku = offer.sku.take().or_else(|| Some(value.to_string())),
+                name if GTIN_PROPERTIES.contains(&name) => {
+                    offer.gtin = offer.gtin.take().or_else(|| Some(value.to_string()))
+                }
+                _ => {}
+            }
+        }
+    }
+    offer.currency = offer.currency.or(implied_currency);
+    offer
+}
+
+/// Product offers of a page, merged from its structured data and product markup
+///
+/// The offers of JSON-LD, else of microdata, are returned in document order, an
+/// AggregateOffer counting as one. Fields missing from the first offer are then taken from
+/// the first microdata offer, OpenGraph `product:price:*` tags and `itemprop` markup, in that
+/// order; `sources` records where each field came from.
+pub fn html_offers(html: &str) -> Vec<Offer> {
+    let jsonld = structured_offers(&jsonld_nodes(html, None));
+    let microdata = structured_offers(&microdata_items(html));
+
+    let (mut offers, fallback) = if jsonld.is_empty() {
+        let offers = microdata
+            .into_iter()
+            .map(|offer| Offer::from_source(offer, "microdata"))
+            .collect();
+        (offers, None)
+    } else {
+        let offers: Vec<Offer> = jsonld
+            .into_iter()
+            .map(|offer| Offer::from_source(offer, "json-ld"))
+            .collect();
+        (offers, microdata.into_iter().next())
+    };
+
+    let mut first = if offers.is_empty() {
+        Offer::default()
+    } else {
+        offers.remove(0)
+    };
+    if let Some(fallback) = fallback {
+        first.merge(fallback, "microdata");
+    }
+    first.merge(opengraph_offer(html), "opengraph");
+    first.merge(itemprop_offer(html), "itemprop");
+    if !first.sources.is_empty() {
+        offers.insert(0, first);
+    }
+    offers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(offer: &Offer) -> Vec<(&str, &str)> {
+        offer
+            .sources
+            .iter()
+            .map(|(field, source)| (field.as_str(), source.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_jsonld_offers() {
+        let offers = html_offers(
+            r#"<script type="application/ld+json">{
+                "@context": "https://schema.org",
+                "@type": "Product",
+                "sku": "SKU-1",
+                "gtin13": "4006381333931",
+                "offers": [
+                  {"@type": "Offer", "price": "19.99", "priceCurrency": "EUR",
+                   "availability": "https://schema.org/InStock", "seller": {"@type": "Organization", "name": "Shop"}},
+                  {"@type": "AggregateOffer", "lowPrice": 15, "highPrice": "24.50", "priceCurrency": "EUR"}
+                ]
+            }</script>"#,
+        );
+        assert_eq!(offers.len(), 2);
+        assert_eq!(offers[0].price, Some(19.99));
+        assert_eq!(offers[0].availability.as_deref(), Some("InStock"));
+        assert_eq!(offers[0].sku.as_deref(), Some("SKU-1"));
+        assert_eq!(offers[0].gtin.as_deref(), Some("4006381333931"));
+        assert_eq!(offers[0].seller.as_deref(), Some("Shop"));
+        assert_eq!(
+            (offers[1].price, offers[1].low_price, offers[1].high_price),
+            (None, Some(15.0), Some(24.5))
+        );
+        assert_eq!(
+            sources(&offers[1]),
+            vec![
+                ("low_price", "json-ld"),
+                ("high_price", "json-ld"),
+                ("currency", "json-ld"),
+                ("sku", "json-ld"),
+                ("gtin", "json-ld"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallback_sources() {
+        let offers = html_offers(
+            r#"<head>
+                 <meta property="product:price:amount" content="1299.00">
+                 <meta property="product:price:currency" content="SEK">
+                 <meta property="product:availability" content="oos">
+               </head>
+               <body>
+                 <div itemscope itemtype="https://schema.org/Product">
+                   <span itemprop="name">Chair</span>
+                   <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                     <span itemprop="price" content="1199">Call for price</span>
+                   </div>
+                 </div>
+                 <span itemprop="sku">CH-9</span>
+               </body>"#,
+        );
+        assert_eq!(offers.len(), 1);
+        let offer = &offers[0];
+        assert_eq!(offer.price, Some(1199.0));
+        assert_eq!(offer.currency.as_deref(), Some("SEK"));
+        assert_eq!(offer.availability.as_deref(), Some("OutOfStock"));
+        assert_eq!(offer.sku.as_deref(), Some("CH-9"));
+        assert_eq!(
+            sources(offer),
+            vec![
+                ("price", "microdata"),
+                ("currency", "opengraph"),
+                ("availability", "opengraph"),
+                ("sku", "itemprop"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_loose_itemprop_markup() {
+        let offers = html_offers(
+            r#"<p>Price: <span itemprop="price">€1.299,00</span>
+               <link itemprop="availability" href="http://schema.org/PreOrder"></p>"#,
+        );
+        assert_eq!(offers[0].price, Some(1299.0));
+        assert_eq!(offers[0].currency.as_deref(), Some("EUR"));
+        assert_eq!(offers[0].availability.as_deref(), Some("PreOrder"));
+        assert_eq!(html_offers("<p>No price</p>"), Vec::new());
+    }
+}
@@ -0,0 +1,330 @@
+use crate::link::{detect_base, resolve_url};
+use crate::microdata::{add_property, SCHEMA_ORG};
+use crate::{attribute, collapse_whitespace, parse_document, QueryOptions};
+use kuchikiki::NodeRef;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use url::Url;
+
+/// Prefixes of the RDFa initial context that job pages commonly rely on
+const DEFAULT_PREFIXES: &[(&str, &str)] = &[
+    ("schema", "http://schema.org/"),
+    ("dc", "http://purl.org/dc/terms/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("og", "http://ogp.me/ns#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+fn is_schema_iri(iri: &str) -> bool {
+    SCHEMA_ORG.iter().any(|prefix| iri.starts_with(prefix))
+}
+
+/// Vocabulary and prefixes in effect for an element
+#[derive(Clone)]
+struct Context {
+    vocab: Option<String>,
+    prefixes: HashMap<String, String>,
+    /// Whether an `https://schema.org` `@context` applies, compacting schema.org IRIs
+    in_schema: bool,
+}
+
+impl Context {
+    /// Context of `node`, from its `vocab` and `prefix` attributes
+    fn enter(&self, node: &NodeRef) -> Context {
+        let mut context = self.clone();
+        if let Some(vocab) = attribute(node, "vocab") {
+            let vocab = vocab.trim();
+            context.vocab = (!vocab.is_empty()).then(|| vocab.to_string());
+        }
+        if let Some(prefixes) = attribute(node, "prefix") {
+            // "og: http://ogp.me/ns# dc: http://purl.org/dc/terms/"
+            let mut tokens = prefixes.split_whitespace();
+            while let (Some(name), Some(iri)) = (tokens.next(), tokens.next()) {
+                if let Some(name) = name.strip_suffix(':') {
+                    context
+                        .prefixes
+                        .insert(name.to_ascii_lowercase(), iri.to_string());
+                }
+            }
+        }
+        context
+    }
+
+    /// Expand a CURIE (`og:title`), vocabulary term (`title`) or IRI to a full IRI
+    fn expand_term(&self, term: &str) -> String {
+        if let Some((prefix, reference)) = term.split_once(':') {
+            if let Some(iri) = self.prefixes.get(&prefix.to_ascii_lowercase()) {
+                return format!("{}{}", iri, reference);
+            }
+            return term.to_string();
+        }
+        match &self.vocab {
+            Some(vocab) => format!("{}{}", vocab, term),
+            None => term.to_string(),
+        }
+    }
+
+    /// Expand a `resource` value: a safe CURIE (`[og:x]`), a CURIE or a possibly relative IRI
+    fn expand_resource(&self, value: &str, base: Option<&Url>) -> String {
+        let value = value.trim();
+        if let Some(curie) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            return self.expand_term(curie);
+        }
+        if let Some((prefix, reference)) = value.split_once(':') {
+            if let Some(iri) = self.prefixes.get(&prefix.to_ascii_lowercase()) {
+                return format!("{}{}", iri, reference);
+            }
+        }
+        resolve_url(base, value)
+    }
+
+    /// Compact schema.org IRIs to plain terms when the schema.org context applies
+    fn compact(&self, iri: String) -> String {
+        if !self.in_schema {
+            return iri;
+        }
+        SCHEMA_ORG
+            .iter()
+            .find_map(|prefix| iri.strip_prefix(prefix))
+            .map(str::to_string)
+            .unwrap_or(iri)
+    }
+}
+
+struct Walker {
+    base: Option<Url>,
+    items: Vec<Value>,
+}
+
+impl Walker {
+    /// Literal or IRI value of a `property` element that doesn't start a new item
+    fn property_value(&self, node: &NodeRef, context: &Context) -> String {
+        if let Some(content) = attribute(node, "content") {
+            return content;
+        }
+        if let Some(resource) = attribute(node, "resource") {
+            return context.expand_resource(&resource, self.base.as_ref());
+        }
+        for name in ["href", "src"] {
+            if let Some(url) = attribute(node, name) {
+                return resolve_url(self.base.as_ref(), &url);
+            }
+        }
+        let is_time = node
+            .as_element()
+            .is_some_and(|element| &*element.name.local == "time");
+        match attribute(node, "datetime").filter(|_| is_time) {
+            Some(datetime) => datetime,
+            None => collapse_whitespace(&node.text_contents()),
+        }
+    }
+
+    /// New item for an element with `typeof`
+    fn new_item(&self, node: &NodeRef, types: &str, context: &mut Context) -> Map<String, Value> {
+        let mut object = Map::new();
+        let types: Vec<String> = types
+            .split_whitespace()
+            .map(|term| context.expand_term(term))
+            .collect();
+
+        let is_schema = match types.first() {
+            Some(first) => is_schema_iri(first),
+            None => context.vocab.as_deref().is_some_and(is_schema_iri),
+        };
+        if is_schema && !context.in_schema {
+            object.insert("@context".into(), "https://schema.org".into());
+            context.in_schema = true;
+        }
+
+        let types: Vec<Value> = types
+            .into_iter()
+            .map(|iri| Value::String(context.compact(iri)))
+            .collect();
+        match types.len() {
+            0 => {}
+            1 => {
+                object.insert("@type".into(), types[0].clone());
+            }
+            _ => {
+                object.insert("@type".into(), Value::Array(types));
+            }
+        }
+        if let Some(resource) = attribute(node, "resource") {
+            object.insert(
+                "@id".into(),
+                context
+                    .expand_resource(&resource, self.base.as_ref())
+                    .into(),
+            );
+        }
+        object
+    }
+
+    fn walk(&mut self, node: &NodeRef, context: &Context, item: Option<&mut Map<String, Value>>) {
+        if node.as_element().is_none() {
+            for child in node.children() {
+                self.walk(&child, context, None);
+            }
+            return;
+        }
+
+        let mut context = context.enter(node);
+        let properties: Vec<String> = attribute(node, "property")
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        // Properties only attach to an enclosing item, and are resolved in its context
+        let property_names: Vec<String> = properties
+            .iter()
+            .map(|term| context.compact(context.expand_term(term)))
+            .collect();
+
+        let Some(types) = attribute(node, "typeof") else {
+            let mut item = item;
+            if let Some(item) = item.as_deref_mut() {
+                if !property_names.is_empty() {
+                    let value = Value::String(self.property_value(node, &context));
+                    for name in &property_names {
+                        add_property(item, name, value.clone());
+                    }
+                }
+            }
+            for child in node.children() {
+                self.walk(&child, &context, item.as_deref_mut());
+            }
+            return;
+        };
+
+        let parent = item.filter(|_| !property_names.is_empty());
+        // Top-level items keep document order, ahead of the unattached items inside them
+        let slot = parent.is_none().then(|| {
+            context.in_schema = false;
+            self.items.push(Value::Null);
+            self.items.len() - 1
+        });
+
+        let mut object = self.new_item(node, &types, &mut context);
+        for child in node.children() {
+            self.walk(&child, &context, Some(&mut object));
+        }
+        match (parent, slot) {
+            (Some(parent), _) => {
+                for name in &property_names {
+                    add_property(parent, name, Value::Object(object.clone()));
+                }
+            }
+            (None, Some(slot)) => self.items[slot] = Value::Object(object),
+            (None, None) => {}
+        }
+    }
+}
+
+/// Extract RDFa Lite items (`vocab`, `typeof`, `property`, `resource`, `prefix`) as
+/// JSON-LD shaped objects
+///
+/// Each `typeof` element starts an item, nested in the enclosing item when it also has a
+/// `property`, or top-level otherwise. Terms are expanded with `vocab` and prefixes, and
+/// schema.org IRIs compacted under an `https://schema.org` `@context`. `resource`, `href`
+/// and `src` values are resolved against `<base>`.
+pub fn rdfa_items(html: &str) -> Vec<Value> {
+    let document = parse_document(html, &QueryOptions::default());
+    let context = Context {
+        vocab: None,
+        prefixes: DEFAULT_PREFIXES
+            .iter()
+            .map(|(name, iri)| (name.to_string(), iri.to_string()))
+            .collect(),
+        in_schema: false,
+    };
+    let mut walker = Walker {
+        base: detect_base(&document),
+        items: Vec::new(),
+    };
+    walker.walk(&document, &context, None);
+    walker.items
+}
+
+/// [`rdfa_items`] as a JSON array
+pub fn html_rdfa(html: &str) -> String {
+    Value::Array(rdfa_items(html)).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rdfa(html: &str) -> Value {
+        serde_json::from_str(&html_rdfa(html)).unwrap()
+    }
+
+    #[test]
+    fn test_schema_vocab() {
+        let value = rdfa(
+            r#"<div vocab="https://schema.org/" typeof="JobPosting">
+                 <h1 property="title">Research  Assistant</h1>
+                 <time property="datePosted" datetime="2024-05-01">May 1</time>
+                 <span property="employmentType" content="PART_TIME">Part time</span>
+                 <div property="hiringOrganization" typeof="CollegeOrUniversity">
+                   <span property="name">Example University</span>
+                 </div>
+                 <span property="skills">R</span><span property="skills">Python</span>
+               </div>"#,
+        );
+        assert_eq!(
+            value,
+            json!([{
+                "@context": "https://schema.org",
+                "@type": "JobPosting",
+                "title": "Research Assistant",
+                "datePosted": "2024-05-01",
+                "employmentType": "PART_TIME",
+                "hiringOrganization": {"@type": "CollegeOrUniversity", "name": "Example University"},
+                "skills": ["R", "Python"]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_prefixes_and_resources() {
+        let value = rdfa(
+            r#"<base href="https://jobs.example.gov/list/">
+               <div prefix="ex: https://example.org/ns#" typeof="ex:Vacancy" resource="42">
+                 <a property="ex:apply" href="apply">Apply</a>
+                 <span property="dc:title">Clerk</span>
+                 <span property="ex:grade" resource="[ex:GS-5]"></span>
+               </div>"#,
+        );
+        assert_eq!(
+            value,
+            json!([{
+                "@type": "https://example.org/ns#Vacancy",
+                "@id": "https://jobs.example.gov/list/42",
+                "https://example.org/ns#apply": "https://jobs.example.gov/list/apply",
+                "http://purl.org/dc/terms/title": "Clerk",
+                "https://example.org/ns#grade": "https://example.org/ns#GS-5"
+            }])
+        );
+    }
+
+    #[test]
+    fn test_unattached_items_are_top_level() {
+        let value = rdfa(
+            r#"<body vocab="http://schema.org/">
+                 <div typeof="Organization"><span property="name">Acme</span>
+                   <div typeof="Place"><span property="address">Main St</span></div>
+                 </div>
+                 <p property="name">No item</p>
+               </body>"#,
+        );
+        assert_eq!(
+            value,
+            json!([
+                {"@type": "Organization", "@context": "https://schema.org", "name": "Acme"},
+                {"@type": "Place", "@context": "https://schema.org", "address": "Main St"}
+            ])
+        );
+        assert_eq!(rdfa("<p>No RDFa</p>"), json!([]));
+    }
+}
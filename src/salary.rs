@@ -0,0 +1,757 @@
+use serde_json::json;
+
+/// ISO 4217 codes recognized in salary text
+const CURRENCY_CODES: &[&str] = &[
+    "AUD", "BRL", "CAD", "CHF", "CNY", "CZK", "DKK", "EUR", "GBP", "HKD", "INR", "ISK", "JPY",
+    "MXN", "NOK", "NZD", "PLN", "SEK", "SGD", "USD", "ZAR",
+];
+
+/// Currency symbols, longest first so `US$` wins over `$`
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("US$", "USD"),
+    ("AU$", "AUD"),
+    ("CA$", "CAD"),
+    ("NZ$", "NZD"),
+    ("HK$", "HKD"),
+    ("R$", "BRL"),
+    ("A$", "AUD"),
+    ("C$", "CAD"),
+    ("S$", "SGD"),
+    ("zł", "PLN"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("$", "USD"),
+];
+
+/// Period keywords in English, German, French, Spanish and the Nordic languages
+const PERIODS: &[(&str, &[&str])] = &[
+    (
+        "HOUR",
+        &[
+            "hour",
+            "hours",
+            "hourly",
+            "hr",
+            "hrs",
+            "h",
+            "stunde",
+            "stunden",
+            "std",
+            "stündlich",
+            "heure",
+            "heures",
+            "hora",
+            "horas",
+            "timme",
+            "timmen",
+            "timmar",
+            "timen",
+            "timer",
+            "timelønn",
+            "timeløn",
+            "timlön",
+            "tunti",
+            "tunnissa",
+        ],
+    ),
+    (
+        "DAY",
+        &[
+            "day",
+            "days",
+            "daily",
+            "tag",
+            "tagessatz",
+            "täglich",
+            "jour",
+            "journée",
+            "día",
+            "dia",
+            "diario",
+            "dag",
+            "dagen",
+            "päivä",
+        ],
+    ),
+    (
+        "WEEK",
+        &[
+            "week",
+            "weeks",
+            "weekly",
+            "wk",
+            "woche",
+            "wöchentlich",
+            "semaine",
+            "semana",
+            "vecka",
+            "veckan",
+            "uke",
+            "uka",
+            "uge",
+            "viikko",
+        ],
+    ),
+    (
+        "MONTH",
+        &[
+            "month",
+            "months",
+            "monthly",
+            "mo",
+            "mth",
+            "monat",
+            "monatlich",
+            "mtl",
+            "mois",
+            "mensuel",
+            "mensuelle",
+            "mes",
+            "mensual",
+            "mån",
+            "månad",
+            "månaden",
+            "månadslön",
+            "måned",
+            "måneden",
+            "månedlig",
+            "mnd",
+            "kk",
+            "kuukausi",
+            "kuussa",
+        ],
+    ),
+    (
+        "YEAR",
+        &[
+            "year",
+            "years",
+            "yearly",
+            "annual",
+            "annually",
+            "annum",
+            "yr",
+            "pa",
+            "jahr",
+            "jährlich",
+            "jahresgehalt",
+            "année",
+            "annuel",
+            "annuelle",
+            "año",
+            "anual",
+            "år",
+            "året",
+            "årlig",
+            "årslön",
+            "årsløn",
+            "vuosi",
+            "vuodessa",
+        ],
+    ),
+];
+
+/// Words tying a period keyword to the amount before it: "per year", "im Monat", "i timen"
+const PERIOD_CONNECTORS: &[&str] = &[
+    "per", "a", "an", "every", "im", "pro", "je", "par", "au", "al", "por", "el", "i", "om", "pr",
+];
+
+/// Words marking an amount as an estimate
+const ESTIMATE_WORDS: &[&str] = &[
+    "estimated",
+    "estimate",
+    "approx",
+    "approximately",
+    "about",
+    "around",
+    "circa",
+    "ca",
+    "etwa",
+    "ungefähr",
+    "rund",
+    "environ",
+    "aprox",
+    "aproximadamente",
+    "alrededor",
+    "ungefär",
+    "omkring",
+    "cirka",
+    "noin",
+];
+
+/// Words before a single amount making it the upper bound, "up to 80k"
+const UPPER_BOUND_WORDS: &[&str] = &[
+    "up to",
+    "upto",
+    "max",
+    "maximum",
+    "bis zu",
+    "bis",
+    "höchstens",
+    "jusqu'à",
+    "jusqu’à",
+    "hasta",
+    "upp till",
+    "opp til",
+    "op til",
+    "enintään",
+];
+
+/// Words before a single amount making it the lower bound, "from 50k"
+const LOWER_BOUND_WORDS: &[&str] = &[
+    "from",
+    "starting at",
+    "starting from",
+    "min",
+    "minimum",
+    "at least",
+    "ab",
+    "mindestens",
+    "à partir de",
+    "a partir de",
+    "dès",
+    "desde",
+    "från",
+    "fra",
+    "fraa",
+    "alkaen",
+    "vähintään",
+];
+
+/// Words between the two amounts of a range
+const RANGE_WORDS: &[&str] = &[
+    "-", "–", "—", "~", "to", "and", "bis", "und", "à", "a", "et", "y", "hasta", "till", "til",
+    "och", "og", "ja",
+];
+
+/// A salary parsed from text
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ParsedSalary {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// ISO 4217 code
+    pub currency: Option<String>,
+    /// `HOUR`, `DAY`, `WEEK`, `MONTH` or `YEAR`
+    pub period: Option<String>,
+    pub is_estimate: bool,
+}
+
+impl ParsedSalary {
+    pub fn to_json(&self) -> String {
+        json!({
+            "min": self.min,
+            "max": self.max,
+            "currency": self.currency,
+            "period": self.period,
+            "is_estimate": self.is_estimate,
+        })
+        .to_string()
+    }
+}
+
+#[derive(Debug)]
+struct Amount {
+    value: f64,
+    /// Scale of a `k` or `m` suffix
+    multiplier: Option<f64>,
+    start: usize,
+    end: usize,
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\u{a0}' || c == '\u{202f}'
+}
+
+/// Parse digits with grouping and decimal separators in any locale: `80.000`, `80,000`,
+/// `1.234,56`, `1,234.56`, `55.5`
+fn parse_number(token: &str) -> Option<f64> {
+    let token: String = token
+        .chars()
+        .filter(|c| !is_space(*c) && *c != '\'')
+        .collect();
+    let dots = token.matches('.').count();
+    let commas = token.matches(',').count();
+    let normalized = match (dots, commas) {
+        (0, 0) => token,
+        (_, 0) | (0, _) => {
+            let separator = if dots > 0 { '.' } else { ',' };
+            let (_, last_group) = token.rsplit_once(separator)?;
+            // One separator before exactly three digits groups thousands
+            if dots + commas > 1 || last_group.len() == 3 {
+                token.replace(separator, "")
+            } else {
+                token.replace(separator, ".")
+            }
+        }
+        _ => {
+            // The last separator is the decimal one
+            let decimal = if token.rfind('.') > token.rfind(',') {
+                '.'
+            } else {
+                ','
+            };
+            let grouping = if decimal == '.' { ',' } else { '.' };
+            token.replace(grouping, "").replace(decimal, ".")
+        }
+    };
+    normalized.parse().ok()
+}
+
+/// Find the amounts of a text with their `k`/`m` suffixes applied
+fn scan_amounts(text: &str) -> Vec<Amount> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut amounts = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        // Digits glued to a word aren't an amount, unless the word is a currency code
+        // as in "EUR60000"
+        let letters = chars[..i]
+            .iter()
+            .rev()
+            .take_while(|(_, c)| c.is_alphabetic())
+            .count();
+        let glued_code = letters > 0
+            && (letters == i || !chars[i - letters - 1].1.is_alphanumeric())
+            && CURRENCY_CODES.contains(&text[chars[i - letters].0..start].to_uppercase().as_str());
+        let after_word = i > 0 && chars[i - 1].1.is_alphanumeric() && !glued_code;
+        if !c.is_ascii_digit() || after_word {
+            i += 1;
+            continue;
+        }
+
+        let mut j = i;
+        while j < chars.len() {
+            let c = chars[j].1;
+            if c.is_ascii_digit() {
+                j += 1;
+                continue;
+            }
+            let next_is_digit = chars.get(j + 1).is_some_and(|(_, c)| c.is_ascii_digit());
+            if (c == '.' || c == ',' || c == '\'') && next_is_digit {
+                j += 1;
+                continue;
+            }
+            // A space only groups thousands, "80 000"
+            let group = chars[j + 1..]
+                .iter()
+                .take_while(|(_, c)| c.is_ascii_digit())
+                .count();
+            let group_ends = chars
+                .get(j + 1 + group)
+                .is_none_or(|(_, c)| !c.is_ascii_digit());
+            if is_space(c) && group == 3 && group_ends {
+                j += 1;
+                continue;
+            }
+            break;
+        }
+        let token_end = chars.get(j).map_or(text.len(), |(index, _)| *index);
+        let Some(mut value) = parse_number(&text[start..token_end]) else {
+            i = j;
+            continue;
+        };
+
+        // Suffix, possibly after a space: "55k", "1,5 Mio"
+        let mut k = j;
+        while chars.get(k).is_some_and(|(_, c)| is_space(*c)) {
+            k += 1;
+        }
+        let suffix_end = chars[k..]
+            .iter()
+            .position(|(_, c)| !c.is_alphabetic())
+            .map_or(chars.len(), |offset| k + offset);
+        let suffix: String = chars[k..suffix_end]
+            .iter()
+            .map(|(_, c)| c.to_ascii_lowercase())
+            .collect();
+        let multiplier = match suffix.as_str() {
+            "k" | "tsd" => Some(1e3),
+            "m" | "mio" | "mn" | "million" | "millionen" | "millions" => Some(1e6),
+            _ => None,
+        };
+        let mut end = token_end;
+        if let Some(multiplier) = multiplier {
+            value *= multiplier;
+            end = chars
+                .get(suffix_end)
+                .map_or(text.len(), |(index, _)| *index);
+        }
+
+        amounts.push(Amount {
+            value,
+            multiplier,
+            start,
+            end,
+        });
+        i = suffix_end.max(j);
+    }
+    amounts
+}
+
+/// First amount of a text, "€1.299,00" -> 1299
+pub(crate) fn parse_amount(text: &str) -> Option<f64> {
+    scan_amounts(text).first().map(|amount| amount.value)
+}
+
+/// ISO code of the first currency code, else symbol, of a text
+pub(crate) fn currency(text: &str) -> Option<String> {
+    let code = text
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .map(|word| word.to_ascii_uppercase())
+        .find(|word| CURRENCY_CODES.contains(&word.as_str()));
+    code.or_else(|| {
+        CURRENCY_SYMBOLS
+            .iter()
+            .find(|(symbol, _)| text.contains(symbol))
+            .map(|(_, code)| code.to_string())
+    })
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Lowercase text with its currency symbols replaced by spaces
+fn without_currency_symbols(text: &str) -> String {
+    let mut text = text.to_lowercase();
+    for (symbol, _) in CURRENCY_SYMBOLS {
+        text = text.replace(&symbol.to_lowercase(), " ");
+    }
+    text
+}
+
+/// Whether the text between an amount and a period keyword after it ties the two: no other
+/// number, and a connector like "per" or "/" unless the keyword follows right away. A bare
+/// `h` must follow right away, so "H/F" in French job titles isn't an hourly rate.
+fn ties_period(between: &str, is_bare_h: bool) -> bool {
+    if between.chars().any(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let between = without_currency_symbols(between);
+    let words: Vec<String> = words(&between)
+        .into_iter()
+        .filter(|word| !CURRENCY_CODES.contains(&word.to_ascii_uppercase().as_str()))
+        .collect();
+    words.is_empty()
+        || (!is_bare_h
+            && (between.contains('/')
+                || words
+                    .iter()
+                    .any(|word| PERIOD_CONNECTORS.contains(&word.as_str()))))
+}
+
+/// Period of the amount at `start..end`: the keyword closest after it when they are tied
+/// by "per", "/" and the like, else the closest before it, so "€60k per year, 40 hours per
+/// week" is a yearly salary
+fn period(text: &str, start: usize, end: usize) -> Option<String> {
+    // Byte offsets of the ASCII lowercase text match those of `text`
+    let lower = text.to_ascii_lowercase();
+    let mut keywords: Vec<(usize, usize, &str)> = ["p.a.", "p. a.", "par an", "/an"]
+        .iter()
+        .flat_map(|pattern| {
+            lower
+                .match_indices(pattern)
+                .map(|(index, pattern)| (index, index + pattern.len(), "YEAR"))
+        })
+        .collect();
+    let mut word_start = None;
+    for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        match (word_start, c.is_alphanumeric()) {
+            (None, true) => word_start = Some(index),
+            (Some(from), false) => {
+                let word = text[from..index].to_lowercase();
+                if let Some((period, _)) = PERIODS
+                    .iter()
+                    .find(|(_, words)| words.contains(&word.as_str()))
+                {
+                    keywords.push((from, index, period));
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    let is_bare_h = |from: usize, to: usize| text[from..to].eq_ignore_ascii_case("h");
+
+    let after = keywords
+        .iter()
+        .filter(|(from, to, _)| {
+            *from >= end && ties_period(&text[end..*from], is_bare_h(*from, *to))
+        })
+        .min_by_key(|(from, _, _)| *from);
+    let before = keywords
+        .iter()
+        .filter(|(from, to, _)| *to <= start && !is_bare_h(*from, *to))
+        .max_by_key(|(_, to, _)| *to);
+    after.or(before).map(|(_, _, period)| period.to_string())
+}
+
+/// Whether the text hedges its amount: "approx.", "ca.", "~", "Est."
+fn is_estimate(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    let abbreviated = lower
+        .match_indices("est.")
+        .any(|(index, _)| !lower[..index].ends_with(|c: char| c.is_alphanumeric()));
+    abbreviated
+        || text.contains('~')
+        // Without currency symbols, "CA$" isn't "ca"
+        || words(&without_currency_symbols(text))
+            .iter()
+            .any(|word| ESTIMATE_WORDS.contains(&word.as_str()))
+}
+
+/// Whether `text` ends with one of the phrases, as whole words
+fn ends_with_phrase(text: &str, phrases: &[&str]) -> bool {
+    let text = text.trim_end().to_lowercase();
+    phrases.iter().any(|phrase| {
+        text.strip_suffix(phrase).is_some_and(|before| {
+            before.is_empty() || before.ends_with(|c: char| !c.is_alphanumeric())
+        })
+    })
+}
+
+/// Text between two amounts with currencies and spaces removed
+fn is_range_separator(between: &str) -> bool {
+    let between = without_currency_symbols(between.trim());
+    let words: Vec<&str> = between
+        .split_whitespace()
+        .filter(|word| !CURRENCY_CODES.contains(&word.to_ascii_uppercase().as_str()))
+        .collect();
+    matches!(words.as_slice(), [word] if RANGE_WORDS.contains(word))
+}
+
+/// Whether a currency symbol or code is right before or after an amount
+fn next_to_currency(text: &str, amount: &Amount) -> bool {
+    let before = text[..amount.start].trim_end();
+    let after = text[amount.end..].trim_start();
+    let is_code = |word: &str| CURRENCY_CODES.contains(&word.to_ascii_uppercase().as_str());
+    let last_word = before
+        .rsplit(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default();
+    let first_word = after
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or_default();
+    CURRENCY_SYMBOLS
+        .iter()
+        .any(|(symbol, _)| before.ends_with(symbol) || after.starts_with(symbol))
+        || is_code(last_word)
+        || is_code(first_word)
+}
+
+/// Parse a salary such as "€55k–70k per year", "$30 - $45/hr" or "bis zu 80.000 € brutto"
+///
+/// Amounts may use `k`/`m` suffixes and any locale's separators; the currency comes from a
+/// symbol or ISO code, else `default_currency`. Returns None when the text has no amount,
+/// as for "Competitive".
+pub fn parse_salary(text: &str, default_currency: Option<&str>) -> Option<ParsedSalary> {
+    let amounts = scan_amounts(text);
+    // The salary starts at the first amount with a currency, so "40h/week, €4000/month"
+    // isn't 40, or at the start of the range that amount ends
+    let mut index = amounts
+        .iter()
+        .position(|amount| next_to_currency(text, amount))
+        .unwrap_or(0);
+    if index > 0 && is_range_separator(&text[amounts[index - 1].end..amounts[index].start]) {
+        index -= 1;
+    }
+    let first = amounts.get(index)?;
+
+    let mut salary = ParsedSalary {
+        currency: currency(text).or_else(|| {
+            default_currency
+                .map(|code| code.trim().to_ascii_uppercase())
+                .filter(|code| !code.is_empty())
+        }),
+        period: None,
+        is_estimate: is_estimate(text),
+        ..Default::default()
+    };
+
+    let range_end = amounts
+        .get(index + 1)
+        .filter(|second| is_range_separator(&text[first.end..second.start]));
+    salary.period = period(text, first.start, range_end.unwrap_or(first).end);
+    match range_end {
+        Some(second) => {
+            let mut low = first.value;
+            // "55-70k" scales both ends
+            if let (None, Some(multiplier)) = (first.multiplier, second.multiplier) {
+                if low * multiplier <= second.value {
+                    low *= multiplier;
+                }
+            }
+            salary.min = Some(low.min(second.value));
+            salary.max = Some(low.max(second.value));
+        }
+        None => {
+            let before = &text[..first.start];
+            let before =
+                before.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’');
+            if ends_with_phrase(before, UPPER_BOUND_WORDS) {
+                salary.max = Some(first.value);
+            } else if ends_with_phrase(before, LOWER_BOUND_WORDS) {
+                salary.min = Some(first.value);
+            } else {
+                salary.min = Some(first.value);
+                salary.max = Some(first.value);
+            }
+        }
+    }
+    Some(salary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn salary(text: &str) -> ParsedSalary {
+        parse_salary(text, None).unwrap()
+    }
+
+    fn range(min: f64, max: f64, currency: &str, period: Option<&str>) -> ParsedSalary {
+        ParsedSalary {
+            min: Some(min),
+            max: Some(max),
+            currency: Some(currency.to_string()),
+            period: period.map(str::to_string),
+            is_estimate: false,
+        }
+    }
+
+    #[test]
+    fn test_number_separators() {
+        assert_eq!(parse_number("80.000"), Some(80000.0));
+        assert_eq!(parse_number("80,000"), Some(80000.0));
+        assert_eq!(parse_number("1.234.567"), Some(1234567.0));
+        assert_eq!(parse_number("1.234,56"), Some(1234.56));
+        assert_eq!(parse_number("1,234.56"), Some(1234.56));
+        assert_eq!(parse_number("45,5"), Some(45.5));
+        assert_eq!(parse_number("80 000"), Some(80000.0));
+        assert_eq!(parse_number("100'000"), Some(100000.0));
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(
+            salary("€55k–70k per year"),
+            range(55000.0, 70000.0, "EUR", Some("YEAR"))
+        );
+        assert_eq!(
+            salary("$30 - $45/hr"),
+            range(30.0, 45.0, "USD", Some("HOUR"))
+        );
+        assert_eq!(
+            salary("55-70k GBP p.a."),
+            range(55000.0, 70000.0, "GBP", Some("YEAR"))
+        );
+        assert_eq!(
+            salary("Zwischen 4.000 und 5.500 EUR brutto im Monat"),
+            range(4000.0, 5500.0, "EUR", Some("MONTH"))
+        );
+        assert_eq!(
+            salary("45 000 – 52 000 kr/mån"),
+            ParsedSalary {
+                min: Some(45000.0),
+                max: Some(52000.0),
+                period: Some("MONTH".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_bounds_and_estimates() {
+        let parsed = salary("bis zu 80.000 € brutto");
+        assert_eq!((parsed.min, parsed.max), (None, Some(80000.0)));
+        assert_eq!(parsed.currency.as_deref(), Some("EUR"));
+
+        let parsed = salary("À partir de 3 200 € par mois");
+        assert_eq!((parsed.min, parsed.max), (Some(3200.0), None));
+        assert_eq!(parsed.period.as_deref(), Some("MONTH"));
+
+        let parsed = salary("Hasta 2.500 € al mes");
+        assert_eq!((parsed.min, parsed.max), (None, Some(2500.0)));
+
+        let parsed = salary("Est. $120K/yr (Glassdoor)");
+        assert!(parsed.is_estimate);
+        assert_eq!(parsed.min, Some(120000.0));
+        assert_eq!(parsed.period.as_deref(), Some("YEAR"));
+
+        let parsed = salary("ca. 1,5 Mio SEK per år");
+        assert_eq!(parsed.min, Some(1500000.0));
+        assert_eq!(parsed.currency.as_deref(), Some("SEK"));
+        assert_eq!(parsed.period.as_deref(), Some("YEAR"));
+        assert!(parsed.is_estimate);
+    }
+
+    #[test]
+    fn test_period_closest_to_amount() {
+        assert_eq!(
+            salary("€60k per year, 40 hours per week").period.as_deref(),
+            Some("YEAR")
+        );
+        assert_eq!(
+            salary("4000 EUR/month, 2 days home office")
+                .period
+                .as_deref(),
+            Some("MONTH")
+        );
+        assert_eq!(
+            salary("Monthly salary: 3 000 EUR").period.as_deref(),
+            Some("MONTH")
+        );
+    }
+
+    #[test]
+    fn test_period_keywords_tied_to_amount() {
+        assert_eq!(salary("Développeur H/F - 45 000 €").period, None);
+        assert_eq!(salary("45 €/h").period.as_deref(), Some("HOUR"));
+        assert_eq!(salary("£40,000 - £45,000 + 5 days holiday").period, None);
+    }
+
+    #[test]
+    fn test_amount_with_currency_preferred() {
+        assert_eq!(
+            salary("40h/week, €4000/month"),
+            range(4000.0, 4000.0, "EUR", Some("MONTH"))
+        );
+        assert_eq!(
+            salary("Team of 12, 55-70k GBP p.a."),
+            range(55000.0, 70000.0, "GBP", Some("YEAR"))
+        );
+        let parsed = salary("CA$80,000 per year");
+        assert!(!parsed.is_estimate);
+        assert_eq!(parsed.currency.as_deref(), Some("CAD"));
+        assert!(salary("ca. 80.000 EUR").is_estimate);
+    }
+
+    #[test]
+    fn test_amount_after_currency_code() {
+        assert_eq!(
+            salary("EUR60000 per year"),
+            range(60000.0, 60000.0, "EUR", Some("YEAR"))
+        );
+        assert_eq!(parse_salary("Version2 release", None), None);
+    }
+
+    #[test]
+    fn test_default_currency_and_no_amount() {
+        let parsed = parse_salary("600 kr i timen", Some("dkk")).unwrap();
+        assert_eq!(parsed.currency.as_deref(), Some("DKK"));
+        assert_eq!(parsed.period.as_deref(), Some("HOUR"));
+        assert_eq!(parse_salary("Competitive", Some("EUR")), None);
+        assert_eq!(
+            salary("30k").to_json(),
+            r#"{"currency":null,"is_estimate":false,"max":30000.0,"min":30000.0,"period":null}"#
+        );
+    }
+}
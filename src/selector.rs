@@ -0,0 +1,593 @@
+use crate::{namespace, template};
+use crate::{ExtractMode, QueryOptions};
+use html5ever::{local_name, LocalName, Namespace};
+use kuchikiki::iter::NodeIterator;
+use kuchikiki::{ElementData, NodeDataRef, NodeRef, Selectors};
+use selectors::context::QuirksMode;
+use selectors::matching::{matches_selector_list, MatchingContext, MatchingMode};
+use selectors::parser::{SelectorList, SelectorParseErrorKind};
+use std::error::Error;
+
+/// kuchikiki's selector implementation, which it doesn't export by name
+type KuchikiSelectorImpl = <NodeDataRef<ElementData> as selectors::Element>::Impl;
+
+/// Selector parser that resolves namespace prefixes such as `svg|use` and `[xlink|href]`
+///
+/// kuchikiki's own parser rejects every namespace prefix. This one doesn't support the
+/// non tree-structural pseudo-classes (`:hover`, `:link`, ...) so it is only used for
+/// selectors that kuchikiki can't parse.
+struct NamespacedParser {
+    prefixes: Vec<(String, Namespace)>,
+}
+
+impl<'i> selectors::parser::Parser<'i> for NamespacedParser {
+    type Impl = KuchikiSelectorImpl;
+    type Error = SelectorParseErrorKind<'i>;
+
+    fn namespace_for_prefix(&self, prefix: &LocalName) -> Option<Namespace> {
+        self.prefixes
+            .iter()
+            .find(|(name, _)| name.as_str() == &**prefix)
+            .map(|(_, ns)| ns.clone())
+    }
+}
+
+/// A compiled selector step
+enum CompiledSelector {
+    Plain(Selectors),
+    Namespaced(SelectorList<KuchikiSelectorImpl>),
+}
+
+impl CompiledSelector {
+    fn compile(selector: &str, root: &NodeRef) -> Result<Self, Box<dyn Error>> {
+        if let Ok(selectors) = Selectors::compile(selector) {
+            return Ok(CompiledSelector::Plain(selectors));
+        }
+
+        if selector.contains('|') {
+            let parser = NamespacedParser {
+                prefixes: namespace::namespace_prefixes(root),
+            };
+            let mut input = cssparser::ParserInput::new(selector);
+            if let Ok(list) = SelectorList::parse(&parser, &mut cssparser::Parser::new(&mut input))
+            {
+                return Ok(CompiledSelector::Namespaced(list));
+            }
+        }
+
+        Err("Failed to parse CSS selector".into())
+    }
+
+    fn matches(&self, element: &NodeDataRef<ElementData>) -> bool {
+        match self {
+            CompiledSelector::Plain(selectors) => selectors.matches(element),
+            CompiledSelector::Namespaced(list) => {
+                let mut context =
+                    MatchingContext::new(MatchingMode::Normal, None, None, QuirksMode::NoQuirks);
+                matches_selector_list(list, element, &mut context)
+            }
+        }
+    }
+
+    /// Matching elements below `scope`, in document order
+    fn select(&self, scope: &NodeRef) -> Vec<NodeRef> {
+        scope
+            .descendants()
+            .elements()
+            .filter(|elem| self.matches(elem))
+            .map(|elem| elem.as_node().clone())
+            .collect()
+    }
+}
+
+/// Split `input` on any of `delims` wherever they appear outside quotes, brackets and
+/// parentheses, returning each part together with the delimiter that preceded it
+fn split_top_level_any<'a, 'd>(
+    input: &'a str,
+    delims: &[&'d str],
+) -> Vec<(Option<&'d str>, &'a str)> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escape_next = false;
+    let mut previous_delim = None;
+    let mut start = 0;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let ch = input[pos..].chars().next().unwrap_or_default();
+
+        if escape_next {
+            escape_next = false;
+        } else if ch == '\\' {
+            escape_next = true;
+        } else if let Some(q) = quote {
+            if ch == q {
+                quote = None;
+            }
+        } else if ch == '"' || ch == '\'' {
+            quote = Some(ch);
+        } else if ch == '[' || ch == '(' {
+            depth += 1;
+        } else if ch == ']' || ch == ')' {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 {
+            if let Some(delim) = delims
+                .iter()
+                .find(|delim| input[pos..].starts_with(**delim))
+            {
+                parts.push((previous_delim, &input[start..pos]));
+                previous_delim = Some(*delim);
+                pos += delim.len();
+                start = pos;
+                continue;
+            }
+        }
+
+        pos += ch.len_utf8();
+    }
+
+    parts.push((previous_delim, &input[start..]));
+    parts
+}
+
+/// Split `input` on `delim` wherever it appears outside quotes, brackets and parentheses
+pub fn split_top_level<'a>(input: &'a str, delim: &str) -> Vec<&'a str> {
+    split_top_level_any(input, &[delim])
+        .into_iter()
+        .map(|(_, part)| part)
+        .collect()
+}
+
+/// How a selector step is joined to the step before it
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    /// First step, matched below the query root
+    Start,
+    /// `>>>`: continue inside template contents and shadow roots
+    Deep,
+    /// `=>`: parse the text of the matched nodes as HTML and continue inside it
+    Reparse,
+}
+
+fn split_steps(alternative: &str) -> Vec<(Combinator, &str)> {
+    split_top_level_any(alternative, &[">>>", "=>"])
+        .into_iter()
+        .map(|(delim, step)| {
+            let combinator = match delim {
+                None => Combinator::Start,
+                Some(">>>") => Combinator::Deep,
+                Some(_) => Combinator::Reparse,
+            };
+            (combinator, step)
+        })
+        .collect()
+}
+
+/// Split a Scrapy/parsel-style pseudo-element off a single selector step
+///
+/// - `h1::text` -> ("h1", Text)
+/// - `a::attr(href)` -> ("a", Attribute("href"))
+fn split_step(step: &str) -> Result<(String, Option<ExtractMode>), Box<dyn Error>> {
+    let trimmed = step.trim();
+    let Some(pos) = split_top_level(trimmed, "::")
+        .first()
+        .map(|head| head.len())
+        .filter(|&len| len < trimmed.len())
+    else {
+        return Ok((trimmed.to_string(), None));
+    };
+
+    let base = trimmed[..pos].trim();
+    let pseudo = trimmed[pos + 2..].trim();
+
+    let mode = if pseudo.eq_ignore_ascii_case("text") {
+        ExtractMode::Text
+    } else if let Some(args) = pseudo
+        .strip_prefix("attr(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    {
+        let name = args.trim().trim_matches(|c| c == '"' || c == '\'');
+        if name.is_empty() {
+            return Err("Empty ::attr() pseudo-element".into());
+        }
+        ExtractMode::Attribute(name.to_string())
+    } else {
+        return Err(format!("Unsupported pseudo-element '::{}'", pseudo).into());
+    };
+
+    // `div ::text` selects text of descendants in parsel, which is what `div` already does here
+    let base = if base.is_empty() { "*" } else { base };
+    Ok((base.to_string(), Some(mode)))
+}
+
+/// Split the pseudo-element off the last step of a single (non-list) selector
+fn split_single(selector: &str) -> Result<(String, Option<ExtractMode>), Box<dyn Error>> {
+    let trimmed = selector.trim();
+    let last_len = split_steps(trimmed)
+        .last()
+        .map(|(_, step)| step.len())
+        .unwrap_or_default();
+    let (head, last) = trimmed.split_at(trimmed.len() - last_len);
+
+    let (base, mode) = split_step(last)?;
+    if head.is_empty() {
+        Ok((base, mode))
+    } else {
+        Ok((format!("{} {}", head.trim_end(), base), mode))
+    }
+}
+
+/// Split Scrapy/parsel-style `::text` and `::attr(name)` pseudo-elements off a selector
+///
+/// Returns the plain CSS selector and the extraction mode the pseudo-element maps to.
+/// Every selector in a comma-separated list must use the same pseudo-element.
+pub fn split_pseudo_element(
+    selector: &str,
+) -> Result<(String, Option<ExtractMode>), Box<dyn Error>> {
+    if !selector.contains("::") {
+        return Ok((selector.to_string(), None));
+    }
+
+    let mut bases = Vec::new();
+    let mut modes = Vec::new();
+    for part in split_top_level(selector, ",") {
+        let (base, mode) = split_single(part)?;
+        bases.push(base);
+        modes.push(mode);
+    }
+
+    let first = modes[0].clone();
+    if modes.iter().any(|mode| *mode != first) {
+        return Err("All selectors in a list must use the same pseudo-element".into());
+    }
+
+    Ok((bases.join(", "), first))
+}
+
+/// Parse the text of `node` as an HTML fragment for the `=>` reparse combinator
+///
+/// `source` picks the text when given. Otherwise the `srcdoc` of an `<iframe>` is used, or
+/// the text content of any other node (e.g. a `<noscript>` body). HTML-escaped markup is
+/// entity-decoded before parsing.
+fn reparse(node: &NodeRef, source: Option<&ExtractMode>, options: &QueryOptions) -> NodeRef {
+    let text = match (source, node.as_element()) {
+        (Some(mode), _) => crate::extract_content(node, mode),
+        (None, Some(elem)) if elem.name.local == local_name!("iframe") => elem
+            .attributes
+            .borrow()
+            .get("srcdoc")
+            .unwrap_or("")
+            .to_string(),
+        (None, _) => node.text_contents(),
+    };
+
+    let text = if !text.contains('<') && text.contains("&lt;") {
+        crate::decode_entities(&text)
+    } else {
+        text
+    };
+
+    crate::parse_fragment(&text, "body", options)
+}
+
+/// Select all elements below `root` matching `selector`
+///
+/// Supports two extra combinators between selector steps:
+/// - `>>>` (deep): `x-card >>> h2` matches `h2` elements inside the template contents and
+///   declarative shadow roots of `x-card` elements.
+/// - `=>` (reparse): `noscript => img` parses the text of each `noscript` as HTML and
+///   matches `img` elements inside it. A `::text` or `::attr(name)` pseudo-element before
+///   `=>` picks the text to parse, e.g. `div::attr(data-html) => a`.
+///
+/// A leading combinator applies to `root` itself. Namespace prefixes (`svg|use`,
+/// `[xlink|href]`) resolve to the well-known namespaces and any `xmlns:` declarations in
+/// the document.
+pub fn select_nodes(
+    root: &NodeRef,
+    selector: &str,
+    options: &QueryOptions,
+) -> Result<Vec<NodeRef>, Box<dyn Error>> {
+    if !selector.contains(">>>") && !selector.contains("=>") {
+        return Ok(CompiledSelector::compile(selector, root)?.select(root));
+    }
+
+    let mut results: Vec<NodeRef> = Vec::new();
+    for alternative in split_top_level(selector, ",") {
+        let mut scopes = vec![root.clone()];
+        let mut source: Option<ExtractMode> = None;
+
+        for (combinator, step) in split_steps(alternative) {
+            scopes = match combinator {
+                Combinator::Start => scopes,
+                Combinator::Deep => scopes.iter().flat_map(template::template_roots).collect(),
+                Combinator::Reparse => scopes
+                    .iter()
+                    .map(|scope| reparse(scope, source.as_ref(), options))
+                    .collect(),
+            };
+
+            let (step, step_source) = split_step(step)?;
+            source = step_source;
+            if step.is_empty() {
+                continue;
+            }
+
+            let compiled = CompiledSelector::compile(&step, root)?;
+            scopes = scopes
+                .iter()
+                .flat_map(|scope| compiled.select(scope))
+                .collect();
+        }
+
+        for node in scopes {
+            if !results.contains(&node) {
+                results.push(node);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    fn split(selector: &str) -> (String, Option<ExtractMode>) {
+        split_pseudo_element(selector).unwrap()
+    }
+
+    #[test]
+    fn test_split_top_level_respects_brackets_and_quotes() {
+        assert_eq!(
+            split_top_level(r#"a[title="x, y"], b:is(c, d), e"#, ","),
+            vec![r#"a[title="x, y"]"#, " b:is(c, d)", " e"]
+        );
+    }
+
+    #[test]
+    fn test_no_pseudo_element() {
+        assert_eq!(split("div > p"), ("div > p".to_string(), None));
+    }
+
+    #[test]
+    fn test_text_pseudo_element() {
+        assert_eq!(
+            split("h1::text"),
+            ("h1".to_string(), Some(ExtractMode::Text))
+        );
+    }
+
+    #[test]
+    fn test_attr_pseudo_element() {
+        assert_eq!(
+            split("a.next::attr(href)"),
+            (
+                "a.next".to_string(),
+                Some(ExtractMode::Attribute("href".to_string()))
+            )
+        );
+        assert_eq!(
+            split("img::attr('data-src')"),
+            (
+                "img".to_string(),
+                Some(ExtractMode::Attribute("data-src".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_descendant_text_pseudo_element() {
+        assert_eq!(
+            split("div ::text"),
+            ("div".to_string(), Some(ExtractMode::Text))
+        );
+        assert_eq!(split("::text"), ("*".to_string(), Some(ExtractMode::Text)));
+    }
+
+    #[test]
+    fn test_selector_list() {
+        assert_eq!(
+            split("h1::text, h2::text"),
+            ("h1, h2".to_string(), Some(ExtractMode::Text))
+        );
+        assert!(split_pseudo_element("h1::text, a::attr(href)").is_err());
+    }
+
+    #[test]
+    fn test_double_colon_inside_attribute_value() {
+        assert_eq!(
+            split(r#"a[href="std::fmt"]"#),
+            (r#"a[href="std::fmt"]"#.to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_pseudo_element() {
+        assert!(split_pseudo_element("p::before").is_err());
+        assert!(split_pseudo_element("a::attr()").is_err());
+    }
+
+    const SHADOW_HTML: &str = r#"<x-card><template shadowrootmode="open"><h2>Card</h2><x-badge><template shadowrootmode="open"><span>New</span></template></x-badge></template></x-card><h2>Light</h2>"#;
+
+    fn texts(nodes: Vec<NodeRef>) -> Vec<String> {
+        nodes.iter().map(|node| node.text_contents()).collect()
+    }
+
+    #[test]
+    fn test_select_nodes_plain_selector() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        assert_eq!(
+            texts(select_nodes(&doc, "h2", &QueryOptions::default()).unwrap()),
+            vec!["Light"]
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_deep_combinator() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        assert_eq!(
+            texts(select_nodes(&doc, "x-card >>> h2", &QueryOptions::default()).unwrap()),
+            vec!["Card"]
+        );
+        assert_eq!(
+            texts(
+                select_nodes(
+                    &doc,
+                    "x-card >>> x-badge >>> span",
+                    &QueryOptions::default()
+                )
+                .unwrap()
+            ),
+            vec!["New"]
+        );
+        assert!(
+            select_nodes(&doc, "x-card >>> span", &QueryOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_leading_deep_combinator() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        assert_eq!(
+            texts(select_nodes(&doc, ">>> h2", &QueryOptions::default()).unwrap()),
+            vec!["Card"]
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_deep_combinator_in_list() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        assert_eq!(
+            texts(
+                select_nodes(&doc, "body > h2, x-card >>> h2", &QueryOptions::default()).unwrap()
+            ),
+            vec!["Light", "Card"]
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_inlined_templates() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        template::inline_templates(&doc);
+        assert_eq!(
+            texts(select_nodes(&doc, "h2", &QueryOptions::default()).unwrap()),
+            vec!["Card", "Light"]
+        );
+        assert_eq!(
+            texts(select_nodes(&doc, "x-card >>> span", &QueryOptions::default()).unwrap()),
+            vec!["New"]
+        );
+    }
+
+    #[test]
+    fn test_split_pseudo_element_last_step_only() {
+        assert_eq!(
+            split("div::attr(data-html) => a::attr(href)"),
+            (
+                "div::attr(data-html) => a".to_string(),
+                Some(ExtractMode::Attribute("href".to_string()))
+            )
+        );
+        assert_eq!(
+            split("div::attr(data-html) => a"),
+            ("div::attr(data-html) => a".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_reparse_noscript() {
+        let doc = kuchikiki::parse_html()
+            .one(r#"<noscript><img src="/pixel.gif"></noscript><img src="/logo.png">"#);
+        let nodes = select_nodes(&doc, "noscript => img", &QueryOptions::default()).unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].to_string(), r#"<img src="/pixel.gif">"#);
+    }
+
+    #[test]
+    fn test_select_nodes_reparse_iframe_srcdoc() {
+        let doc = kuchikiki::parse_html()
+            .one(r#"<iframe srcdoc="<h1 class='t'>Embedded</h1>"></iframe>"#);
+        assert_eq!(
+            texts(select_nodes(&doc, "iframe => h1.t", &QueryOptions::default()).unwrap()),
+            vec!["Embedded"]
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_reparse_escaped_attribute() {
+        let doc = kuchikiki::parse_html().one(
+            r#"<div data-html="&amp;lt;a href=&amp;quot;/jobs/1&amp;quot;&amp;gt;Job&amp;lt;/a&amp;gt;"></div>"#,
+        );
+        let nodes =
+            select_nodes(&doc, "div::attr(data-html) => a", &QueryOptions::default()).unwrap();
+        assert_eq!(nodes[0].to_string(), r#"<a href="/jobs/1">Job</a>"#);
+    }
+
+    #[test]
+    fn test_select_nodes_leading_reparse() {
+        let doc = kuchikiki::parse_html().one("&lt;ul&gt;&lt;li&gt;One&lt;/li&gt;&lt;/ul&gt;");
+        assert_eq!(
+            texts(select_nodes(&doc, "=> li", &QueryOptions::default()).unwrap()),
+            vec!["One"]
+        );
+    }
+
+    const SVG_HTML: &str = r##"<p><a href="/home">Home</a></p><svg viewBox="0 0 10 10"><use xlink:href="#logo"/><a href="/svg-link">Logo</a><linearGradient id="g"/></svg>"##;
+
+    #[test]
+    fn test_select_nodes_namespace_prefix() {
+        let doc = kuchikiki::parse_html().one(SVG_HTML);
+        let options = QueryOptions::default();
+        assert_eq!(select_nodes(&doc, "svg|use", &options).unwrap().len(), 1);
+        assert_eq!(
+            texts(select_nodes(&doc, "svg|a", &options).unwrap()),
+            vec!["Logo"]
+        );
+        assert_eq!(
+            texts(select_nodes(&doc, "html|a", &options).unwrap()),
+            vec!["Home"]
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_namespaced_attribute() {
+        let doc = kuchikiki::parse_html().one(SVG_HTML);
+        let options = QueryOptions::default();
+        assert_eq!(
+            select_nodes(&doc, "[xlink|href='#logo']", &options)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(select_nodes(&doc, "use[href]", &options)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_select_nodes_camel_cased_svg_names() {
+        let doc = kuchikiki::parse_html().one(SVG_HTML);
+        let options = QueryOptions::default();
+        assert_eq!(
+            select_nodes(&doc, "svg[viewBox]", &options).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            select_nodes(&doc, "svg|linearGradient", &options)
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_select_nodes_unknown_prefix() {
+        let doc = kuchikiki::parse_html().one(SVG_HTML);
+        assert!(select_nodes(&doc, "foo|a", &QueryOptions::default()).is_err());
+    }
+}
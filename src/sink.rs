@@ -0,0 +1,229 @@
+use crate::source::SpanRecorder;
+use html5ever::tendril::StrTendril;
+use html5ever::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use html5ever::{Attribute, ExpandedName, QualName};
+use kuchikiki::NodeRef;
+use std::borrow::Cow;
+
+/// A parse error reported by html5ever or xml5ever
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// 1-based line the parser was on when it reported the error
+    pub line: u64,
+    pub message: String,
+}
+
+/// Builds a kuchikiki tree from html5ever or xml5ever parser events
+///
+/// Mirrors kuchikiki's own sink, which can't be used with xml5ever and discards the line
+/// numbers of parse errors. Parsing returns the sink itself, so both the document and
+/// the errors are available afterwards.
+pub struct NodeSink {
+    pub document: NodeRef,
+    pub errors: Vec<ParseError>,
+    /// Records which elements come from which start tags when parsing with source positions
+    pub spans: Option<SpanRecorder>,
+    line: u64,
+}
+
+impl Default for NodeSink {
+    fn default() -> Self {
+        Self {
+            document: NodeRef::new_document(),
+            errors: Vec::new(),
+            spans: None,
+            line: 1,
+        }
+    }
+}
+
+impl TreeSink for NodeSink {
+    type Output = Self;
+    type Handle = NodeRef;
+
+    fn finish(self) -> Self {
+        self
+    }
+
+    // Parse errors are never fatal, record them and keep going
+    fn parse_error(&mut self, message: Cow<'static, str>) {
+        self.errors.push(ParseError {
+            line: self.line,
+            message: message.into_owned(),
+        });
+    }
+
+    fn set_current_line(&mut self, line_number: u64) {
+        self.line = line_number;
+    }
+
+    fn get_document(&mut self) -> NodeRef {
+        self.document.clone()
+    }
+
+    fn elem_name<'a>(&'a self, target: &'a NodeRef) -> ExpandedName<'a> {
+        target
+            .as_element()
+            .expect("parsers only ask for names of elements")
+            .name
+            .expanded()
+    }
+
+    fn create_element(
+        &mut self,
+        name: QualName,
+        attrs: Vec<Attribute>,
+        _flags: ElementFlags,
+    ) -> NodeRef {
+        let node = NodeRef::new_element(
+            name,
+            attrs.into_iter().map(|attr| {
+                let QualName { prefix, ns, local } = attr.name;
+                (
+                    kuchikiki::ExpandedName { ns, local },
+                    kuchikiki::Attribute {
+                        prefix,
+                        value: String::from(attr.value),
+                    },
+                )
+            }),
+        );
+        if let Some(spans) = &mut self.spans {
+            spans.element_created(&node);
+        }
+        node
+    }
+
+    fn create_comment(&mut self, text: StrTendril) -> NodeRef {
+        NodeRef::new_comment(text)
+    }
+
+    fn create_pi(&mut self, target: StrTendril, data: StrTendril) -> NodeRef {
+        NodeRef::new_processing_instruction(target, data)
+    }
+
+    fn append(&mut self, parent: &NodeRef, child: NodeOrText<NodeRef>) {
+        match child {
+            NodeOrText::AppendNode(node) => parent.append(node),
+            NodeOrText::AppendText(text) => {
+                if let Some(existing) = parent.last_child().as_ref().and_then(|n| n.as_text()) {
+                    existing.borrow_mut().push_str(&text);
+                } else {
+                    parent.append(NodeRef::new_text(text))
+                }
+            }
+        }
+    }
+
+    fn append_based_on_parent_node(
+        &mut self,
+        element: &NodeRef,
+        prev_element: &NodeRef,
+        child: NodeOrText<NodeRef>,
+    ) {
+        if element.parent().is_some() {
+            self.append_before_sibling(element, child)
+        } else {
+            self.append(prev_element, child)
+        }
+    }
+
+    fn append_doctype_to_document(
+        &mut self,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    ) {
+        self.document
+            .append(NodeRef::new_doctype(name, public_id, system_id))
+    }
+
+    // HTML templates get a separate contents fragment, XML ones don't
+    fn get_template_contents(&mut self, target: &NodeRef) -> NodeRef {
+        target
+            .as_element()
+            .and_then(|element| element.template_contents.clone())
+            .unwrap_or_else(|| target.clone())
+    }
+
+    fn same_node(&self, x: &NodeRef, y: &NodeRef) -> bool {
+        x == y
+    }
+
+    fn set_quirks_mode(&mut self, mode: QuirksMode) {
+        if let Some(document) = self.document.as_document() {
+            document._quirks_mode.set(mode);
+        }
+    }
+
+    fn append_before_sibling(&mut self, sibling: &NodeRef, child: NodeOrText<NodeRef>) {
+        match child {
+            NodeOrText::AppendNode(node) => sibling.insert_before(node),
+            NodeOrText::AppendText(text) => {
+                if let Some(existing) = sibling
+                    .previous_sibling()
+                    .as_ref()
+                    .and_then(|n| n.as_text())
+                {
+                    existing.borrow_mut().push_str(&text);
+                } else {
+                    sibling.insert_before(NodeRef::new_text(text))
+                }
+            }
+        }
+    }
+
+    fn add_attrs_if_missing(&mut self, target: &NodeRef, attrs: Vec<Attribute>) {
+        let Some(element) = target.as_element() else {
+            return;
+        };
+        let mut attributes = element.attributes.borrow_mut();
+        for attr in attrs {
+            let QualName { prefix, ns, local } = attr.name;
+            attributes
+                .map
+                .entry(kuchikiki::ExpandedName { ns, local })
+                .or_insert_with(|| kuchikiki::Attribute {
+                    prefix,
+                    value: String::from(attr.value),
+                });
+        }
+    }
+
+    fn remove_from_parent(&mut self, target: &NodeRef) {
+        target.detach()
+    }
+
+    fn reparent_children(&mut self, node: &NodeRef, new_parent: &NodeRef) {
+        for child in node.children().collect::<Vec<_>>() {
+            new_parent.append(child)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use html5ever::tendril::TendrilSink;
+
+    #[test]
+    fn test_html_parse_errors_have_lines() {
+        let sink = html5ever::parse_document(NodeSink::default(), Default::default())
+            .one("<!DOCTYPE html>\n<p>One\n</div>");
+        assert_eq!(
+            sink.document.select_first("p").unwrap().text_contents(),
+            "One\n"
+        );
+        assert_eq!(sink.errors.len(), 1);
+        assert_eq!(sink.errors[0].line, 3);
+    }
+
+    #[test]
+    fn test_html_template_contents() {
+        let sink = html5ever::parse_document(NodeSink::default(), Default::default())
+            .one("<template><p>Inside</p></template>");
+        let template = sink.document.select_first("template").unwrap();
+        let contents = template.template_contents.clone().unwrap();
+        assert_eq!(contents.to_string(), "<p>Inside</p>");
+    }
+}
@@ -0,0 +1,341 @@
+use crate::datetime::parse_datetime;
+use crate::name_of;
+use crate::xml::{child_elements, child_text, parse_xml};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use kuchikiki::NodeRef;
+use std::borrow::Cow;
+use std::error::Error;
+use std::io::Read;
+use url::Url;
+
+/// Largest uncompressed sitemap the protocol allows, 50 MB
+const MAX_UNCOMPRESSED: u64 = 52_428_800;
+
+/// A parsed `sitemap.xml` or sitemap index file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Sitemap {
+    /// True for a `<sitemapindex>`, whose entries point to further sitemaps
+    pub is_index: bool,
+    pub entries: Vec<SitemapEntry>,
+}
+
+/// A `<url>` or, in an index, a `<sitemap>` entry
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+    pub changefreq: Option<String>,
+    pub priority: Option<f64>,
+    pub images: Vec<SitemapImage>,
+    pub news: Option<SitemapNews>,
+    pub videos: Vec<SitemapVideo>,
+    pub alternates: Vec<SitemapAlternate>,
+}
+
+/// Google image sitemap extension (`<image:image>`)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapImage {
+    pub loc: String,
+    pub title: Option<String>,
+    pub caption: Option<String>,
+}
+
+/// Google news sitemap extension (`<news:news>`)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapNews {
+    pub title: Option<String>,
+    pub publication_name: Option<String>,
+    pub publication_language: Option<String>,
+    pub publication_date: Option<DateTime<Utc>>,
+}
+
+/// Google video sitemap extension (`<video:video>`)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapVideo {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub thumbnail_loc: Option<String>,
+    pub content_loc: Option<String>,
+    pub player_loc: Option<String>,
+    /// Duration in seconds
+    pub duration: Option<i64>,
+    pub publication_date: Option<DateTime<Utc>>,
+}
+
+/// Localized version of a page (`<xhtml:link rel="alternate" hreflang="..." href="..."/>`)
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SitemapAlternate {
+    pub hreflang: String,
+    pub href: String,
+}
+
+/// Decompress gzip content (`sitemap.xml.gz`), passing anything else through
+///
+/// Content that decompresses to more than the protocol's 50 MB is an error, so a small
+/// gzip bomb can't exhaust memory.
+pub fn decompress(content: &[u8]) -> Result<Cow<'_, [u8]>, Box<dyn Error>> {
+    if !content.starts_with(&[0x1f, 0x8b]) {
+        return Ok(Cow::Borrowed(content));
+    }
+
+    let mut decompressed = Vec::new();
+    GzDecoder::new(content)
+        .take(MAX_UNCOMPRESSED + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() as u64 > MAX_UNCOMPRESSED {
+        return Err("Sitemap is larger than 50 MB uncompressed".into());
+    }
+    Ok(Cow::Owned(decompressed))
+}
+
+/// Parse a sitemap or sitemap index, optionally gzip-compressed
+///
+/// Entries whose `<loc>` is not an absolute http(s) URL are skipped, as are images and
+/// alternates with invalid URLs.
+pub fn parse_sitemap(content: &[u8]) -> Result<Sitemap, Box<dyn Error>> {
+    let content = decompress(content)?;
+    let text = String::from_utf8_lossy(&content);
+    let document = parse_xml(text.trim_start_matches('\u{feff}'));
+
+    let root = child_elements(&document)
+        .next()
+        .ok_or("Sitemap is not an XML document")?;
+    let (is_index, entry_name) = match name_of(&root).as_str() {
+        "urlset" => (false, "url"),
+        "sitemapindex" => (true, "sitemap"),
+        name => {
+            return Err(format!("Expected <urlset> or <sitemapindex>, found <{}>", name).into())
+        }
+    };
+
+    let entries = child_elements(&root)
+        .filter(|node| name_of(node) == entry_name)
+        .filter_map(|node| parse_entry(&node))
+        .collect();
+
+    Ok(Sitemap { is_index, entries })
+}
+
+fn parse_entry(node: &NodeRef) -> Option<SitemapEntry> {
+    let mut entry = SitemapEntry {
+        loc: valid_url(child_text(node, "loc")?)?,
+        lastmod: child_text(node, "lastmod").and_then(|text| parse_datetime(&text)),
+        changefreq: child_text(node, "changefreq").map(|text| text.to_lowercase()),
+        priority: child_text(node, "priority")
+            .and_then(|text| text.parse().ok())
+            .filter(|priority| (0.0..=1.0).contains(priority)),
+        ..Default::default()
+    };
+
+    // Extensions are matched by local name, as some generators forget to declare
+    // their namespaces
+    for child in child_elements(node) {
+        match name_of(&child).as_str() {
+            "image" => entry.images.extend(parse_image(&child)),
+            "news" => entry.news = Some(parse_news(&child)),
+            "video" => entry.videos.push(parse_video(&child)),
+            "link" => entry.alternates.extend(parse_alternate(&child)),
+            _ => {}
+        }
+    }
+
+    Some(entry)
+}
+
+fn parse_image(node: &NodeRef) -> Option<SitemapImage> {
+    Some(SitemapImage {
+        loc: valid_url(child_text(node, "loc")?)?,
+        title: child_text(node, "title"),
+        caption: child_text(node, "caption"),
+    })
+}
+
+fn parse_news(node: &NodeRef) -> SitemapNews {
+    let publication = child_elements(node).find(|child| name_of(child) == "publication");
+    SitemapNews {
+        title: child_text(node, "title"),
+        publication_name: publication
+            .as_ref()
+            .and_then(|publication| child_text(publication, "name")),
+        publication_language: publication
+            .as_ref()
+            .and_then(|publication| child_text(publication, "language")),
+        publication_date: child_text(node, "publication_date")
+            .and_then(|text| parse_datetime(&text)),
+    }
+}
+
+fn parse_video(node: &NodeRef) -> SitemapVideo {
+    SitemapVideo {
+        title: child_text(node, "title"),
+        description: child_text(node, "description"),
+        thumbnail_loc: child_text(node, "thumbnail_loc").and_then(valid_url),
+        content_loc: child_text(node, "content_loc").and_then(valid_url),
+        player_loc: child_text(node, "player_loc").and_then(valid_url),
+        duration: child_text(node, "duration").and_then(|text| text.parse().ok()),
+        publication_date: child_text(node, "publication_date")
+            .and_then(|text| parse_datetime(&text)),
+    }
+}
+
+fn parse_alternate(node: &NodeRef) -> Option<SitemapAlternate> {
+    let element = node.as_element()?;
+    let attrs = element.attributes.borrow();
+    if !attrs
+        .get("rel")
+        .is_some_and(|rel| rel.eq_ignore_ascii_case("alternate"))
+    {
+        return None;
+    }
+    Some(SitemapAlternate {
+        hreflang: attrs.get("hreflang")?.trim().to_string(),
+        href: valid_url(attrs.get("href")?.to_string())?,
+    })
+}
+
+/// Keep absolute http(s) URLs, normalized by the url crate
+fn valid_url(text: String) -> Option<String> {
+    Url::parse(text.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const URLSET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1"
+        xmlns:news="http://www.google.com/schemas/sitemap-news/0.9"
+        xmlns:video="http://www.google.com/schemas/sitemap-video/1.1"
+        xmlns:xhtml="http://www.w3.org/1999/xhtml">
+  <url>
+    <loc> https://example.com/jobs/1 </loc>
+    <lastmod>2025-01-06T10:00:00+01:00</lastmod>
+    <changefreq>Daily</changefreq>
+    <priority>0.8</priority>
+    <image:image>
+      <image:loc>https://example.com/logo.png</image:loc>
+      <image:caption>Logo</image:caption>
+    </image:image>
+    <xhtml:link rel="alternate" hreflang="de" href="https://example.com/de/jobs/1"/>
+    <xhtml:link rel="alternate" hreflang="fr" href="not a url"/>
+  </url>
+  <url>
+    <loc>https://example.com/news</loc>
+    <news:news>
+      <news:publication>
+        <news:name>Example Times</news:name>
+        <news:language>en</news:language>
+      </news:publication>
+      <news:publication_date>2025-01-05</news:publication_date>
+      <news:title>Hiring</news:title>
+    </news:news>
+    <video:video>
+      <video:thumbnail_loc>https://example.com/thumb.jpg</video:thumbnail_loc>
+      <video:title>Office tour</video:title>
+      <video:duration>120</video:duration>
+    </video:video>
+  </url>
+  <url><loc>/relative</loc></url>
+</urlset>"#;
+
+    const INDEX: &str = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/sitemap-1.xml.gz</loc><lastmod>2025-01</lastmod></sitemap>
+</sitemapindex>"#;
+
+    #[test]
+    fn test_urlset() {
+        let sitemap = parse_sitemap(URLSET.as_bytes()).unwrap();
+        assert!(!sitemap.is_index);
+        assert_eq!(sitemap.entries.len(), 2);
+
+        let entry = &sitemap.entries[0];
+        assert_eq!(entry.loc, "https://example.com/jobs/1");
+        assert_eq!(
+            entry.lastmod.unwrap().to_rfc3339(),
+            "2025-01-06T09:00:00+00:00"
+        );
+        assert_eq!(entry.changefreq.as_deref(), Some("daily"));
+        assert_eq!(entry.priority, Some(0.8));
+        assert_eq!(
+            entry.images,
+            vec![SitemapImage {
+                loc: "https://example.com/logo.png".to_string(),
+                title: None,
+                caption: Some("Logo".to_string()),
+            }]
+        );
+        assert_eq!(
+            entry.alternates,
+            vec![SitemapAlternate {
+                hreflang: "de".to_string(),
+                href: "https://example.com/de/jobs/1".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_news_and_video() {
+        let sitemap = parse_sitemap(URLSET.as_bytes()).unwrap();
+        let entry = &sitemap.entries[1];
+
+        let news = entry.news.as_ref().unwrap();
+        assert_eq!(news.title.as_deref(), Some("Hiring"));
+        assert_eq!(news.publication_name.as_deref(), Some("Example Times"));
+        assert_eq!(news.publication_language.as_deref(), Some("en"));
+        assert!(news.publication_date.is_some());
+
+        assert_eq!(entry.videos.len(), 1);
+        assert_eq!(entry.videos[0].title.as_deref(), Some("Office tour"));
+        assert_eq!(entry.videos[0].duration, Some(120));
+    }
+
+    #[test]
+    fn test_index() {
+        let sitemap = parse_sitemap(INDEX.as_bytes()).unwrap();
+        assert!(sitemap.is_index);
+        assert_eq!(
+            sitemap.entries[0].loc,
+            "https://example.com/sitemap-1.xml.gz"
+        );
+        assert!(sitemap.entries[0].lastmod.is_some());
+    }
+
+    #[test]
+    fn test_gzip() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(INDEX.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let sitemap = parse_sitemap(&compressed).unwrap();
+        assert!(sitemap.is_index);
+        assert_eq!(sitemap.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_gzip_bomb() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        let zeros = vec![0u8; 1 << 20];
+        for _ in 0..=MAX_UNCOMPRESSED >> 20 {
+            encoder.write_all(&zeros).unwrap();
+        }
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < 1 << 20);
+
+        assert!(decompress(&compressed).is_err());
+        assert!(parse_sitemap(&compressed).is_err());
+    }
+
+    #[test]
+    fn test_not_a_sitemap() {
+        assert!(parse_sitemap(b"<rss><channel/></rss>").is_err());
+        assert!(parse_sitemap(b"").is_err());
+    }
+}
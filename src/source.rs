@@ -0,0 +1,570 @@
+use crate::sink::NodeSink;
+use crate::validate::VOID_ELEMENTS;
+use crate::{template, ParserKind, QueryOptions};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+    TokenizerResult,
+};
+use html5ever::tree_builder::{create_element, TreeBuilder, TreeBuilderOpts};
+use html5ever::{namespace_url, ns, LocalName};
+use kuchikiki::iter::NodeEdge;
+use kuchikiki::{Node, NodeRef};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Elements whose contents the tokenizer reads as text up to the matching end tag
+pub(crate) const RAW_TEXT_ELEMENTS: &[&str] = &[
+    "iframe",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+    "script",
+    "style",
+    "textarea",
+    "title",
+    "xmp",
+];
+
+/// Where an element was written in the source document
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceSpan {
+    /// Byte offset of the start tag
+    pub offset: usize,
+    /// Length in bytes, up to the end of the end tag or of the last content when the end
+    /// tag was omitted
+    pub length: usize,
+    /// 1-based line of the start tag
+    pub line: u64,
+    /// 1-based column of the start tag, in characters
+    pub column: u64,
+}
+
+impl SourceSpan {
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"offset\":{},\"length\":{},\"line\":{},\"column\":{}}}",
+            self.offset, self.length, self.line, self.column
+        )
+    }
+}
+
+/// The start tag the tree builder is processing, with its location in the source
+struct CurrentTag {
+    name: LocalName,
+    start: usize,
+    end: usize,
+}
+
+struct RecordedElement {
+    node: NodeRef,
+    start: usize,
+    /// End of the start tag
+    tag_end: usize,
+    /// End of the end tag, or where an unclosed element was implicitly closed
+    end: usize,
+}
+
+/// Records which element the tree builder creates for each start tag, and which end tag
+/// closes it
+///
+/// Elements implied by the parser (e.g. `<tbody>`, or `<html>` for a bare fragment) and
+/// copies made by the adoption agency have no start tag, and are not recorded.
+#[derive(Default)]
+pub struct SpanRecorder {
+    current: Option<CurrentTag>,
+    /// Element created for the current start tag
+    created: Option<usize>,
+    elements: Vec<RecordedElement>,
+    /// Indices of the recorded elements whose end tag hasn't been seen yet
+    open: Vec<usize>,
+}
+
+impl SpanRecorder {
+    pub fn element_created(&mut self, node: &NodeRef) {
+        let Some(tag) = &self.current else {
+            return;
+        };
+        let matches_tag = node
+            .as_element()
+            .is_some_and(|element| element.name.local.eq_ignore_ascii_case(&tag.name));
+        if !matches_tag {
+            return;
+        }
+
+        // Formatting elements reconstructed before the tag's own element have the same
+        // name, the one created last is the tag's
+        if let Some(idx) = self.created {
+            self.elements[idx].node = node.clone();
+            return;
+        }
+        self.created = Some(self.elements.len());
+        self.elements.push(RecordedElement {
+            node: node.clone(),
+            start: tag.start,
+            tag_end: tag.end,
+            end: tag.end,
+        });
+    }
+
+    /// Start a start tag's element, unless it has no contents
+    fn start_tag_processed(&mut self, self_closing: bool) {
+        self.current = None;
+        let Some(idx) = self.created.take() else {
+            return;
+        };
+        let element = self.elements[idx]
+            .node
+            .as_element()
+            .expect("recorded elements");
+        let is_void = if element.name.ns == ns!(html) {
+            VOID_ELEMENTS.contains(&&*element.name.local)
+        } else {
+            self_closing
+        };
+        if !is_void {
+            self.open.push(idx);
+        }
+    }
+
+    /// Close the innermost open element with this name and those opened after it
+    fn end_tag(&mut self, name: &str, start: usize, end: usize) {
+        let Some(pos) = self.open.iter().rposition(|&idx| {
+            self.elements[idx]
+                .node
+                .as_element()
+                .is_some_and(|element| str::eq_ignore_ascii_case(&element.name.local, name))
+        }) else {
+            return;
+        };
+        for idx in self.open.split_off(pos + 1) {
+            self.elements[idx].end = start;
+        }
+        let idx = self.open.pop().expect("position is in the stack");
+        self.elements[idx].end = end;
+    }
+
+    fn input_ended(&mut self, len: usize) {
+        for idx in self.open.drain(..) {
+            self.elements[idx].end = len;
+        }
+    }
+}
+
+/// Source ranges of the elements of a document parsed with [`parse_with_spans`]
+pub struct SourceMap<'a> {
+    source: &'a str,
+    /// Byte range of each node that has one, see [`SourceMap::range`]
+    ranges: HashMap<*const Node, (usize, usize)>,
+    line_starts: Vec<usize>,
+}
+
+fn key(node: &NodeRef) -> *const Node {
+    &**node as *const Node
+}
+
+impl<'a> SourceMap<'a> {
+    /// Compute the range of every node of `document` in two post-order passes
+    ///
+    /// A node's range is its recorded tag's, or that of its children for elements without
+    /// a start tag. Elements whose end tag was omitted end where the next element after
+    /// them in the tree starts, so a `<li>` closed by the next `<li>` doesn't contain it.
+    fn new(source: &'a str, elements: Vec<RecordedElement>, document: &NodeRef) -> Self {
+        let recorded: HashMap<*const Node, RecordedElement> = elements
+            .into_iter()
+            .map(|element| (key(&element.node), element))
+            .collect();
+
+        // Starts don't depend on ends, and the ends need the starts of later nodes
+        let mut starts: HashMap<*const Node, usize> = HashMap::new();
+        for edge in document.traverse_inclusive() {
+            let NodeEdge::End(node) = edge else {
+                continue;
+            };
+            let start = match recorded.get(&key(&node)) {
+                Some(element) => Some(element.start),
+                None => node
+                    .children()
+                    .filter_map(|child| starts.get(&key(&child)).copied())
+                    .min(),
+            };
+            if let Some(start) = start {
+                starts.insert(key(&node), start);
+            }
+        }
+
+        let mut ranges: HashMap<*const Node, (usize, usize)> = HashMap::new();
+        for edge in document.traverse_inclusive() {
+            let NodeEdge::End(node) = edge else {
+                continue;
+            };
+            let children = node
+                .children()
+                .filter_map(|child| ranges.get(&key(&child)).copied())
+                .reduce(|(start, end), (child_start, child_end)| {
+                    (start.min(child_start), end.max(child_end))
+                });
+            let range = match recorded.get(&key(&node)) {
+                None => children,
+                Some(element) => {
+                    let next_start = node
+                        .inclusive_ancestors()
+                        .flat_map(|ancestor| ancestor.following_siblings())
+                        .filter_map(|following| starts.get(&key(&following)).copied())
+                        .find(|&start| start > element.start);
+                    let end = match next_start {
+                        Some(next_start) => element.end.min(next_start),
+                        None => element.end,
+                    };
+                    let end = children.map_or(end, |(_, children_end)| end.max(children_end));
+                    Some((element.start, end.max(element.tag_end)))
+                }
+            };
+            if let Some(range) = range {
+                ranges.insert(key(&node), range);
+            }
+        }
+
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self {
+            source,
+            ranges,
+            line_starts,
+        }
+    }
+
+    /// Byte range of a node, or of its children for elements without a start tag
+    fn range(&self, node: &NodeRef) -> Option<(usize, usize)> {
+        self.ranges.get(&key(node)).copied()
+    }
+
+    /// Location of a node in the source, if it was written there
+    pub fn span(&self, node: &NodeRef) -> Option<SourceSpan> {
+        let (start, end) = self.range(node)?;
+        let line = self
+            .line_starts
+            .partition_point(|&line_start| line_start <= start);
+        let line_start = self.line_starts[line - 1];
+        Some(SourceSpan {
+            offset: start,
+            length: end - start,
+            line: line as u64,
+            column: self.source[line_start..start].chars().count() as u64 + 1,
+        })
+    }
+
+    /// The exact source text of a node, before the parser repaired or normalized it
+    pub fn source_html(&self, node: &NodeRef) -> Option<&'a str> {
+        self.range(node)
+            .map(|(start, end)| &self.source[start..end])
+    }
+}
+
+/// Finds the source location of each tag token on its way into the tree builder
+struct SpanObserver<'a> {
+    tree_builder: TreeBuilder<NodeRef, NodeSink>,
+    source: &'a str,
+    /// Where the search for the next tag starts
+    cursor: usize,
+    /// Raw text element the last start tag opened
+    raw_text: Option<LocalName>,
+}
+
+impl SpanObserver<'_> {
+    fn recorder(&mut self) -> &mut SpanRecorder {
+        self.tree_builder
+            .sink
+            .spans
+            .as_mut()
+            .expect("observed sinks record spans")
+    }
+}
+
+impl TokenSink for SpanObserver<'_> {
+    type Handle = NodeRef;
+
+    fn process_token(&mut self, token: Token, line_number: u64) -> TokenSinkResult<NodeRef> {
+        let Token::TagToken(tag) = &token else {
+            return self.tree_builder.process_token(token, line_number);
+        };
+
+        let raw_text = self.raw_text.take().filter(|name| *name == tag.name);
+        let Some((start, end)) = find_tag(
+            self.source,
+            self.cursor,
+            tag.kind,
+            &tag.name,
+            raw_text.is_some(),
+        ) else {
+            return self.tree_builder.process_token(token, line_number);
+        };
+        self.cursor = end;
+
+        match tag.kind {
+            TagKind::StartTag => {
+                if RAW_TEXT_ELEMENTS.contains(&&*tag.name) {
+                    self.raw_text = Some(tag.name.clone());
+                }
+                let self_closing = tag.self_closing;
+                self.recorder().current = Some(CurrentTag {
+                    name: tag.name.clone(),
+                    start,
+                    end,
+                });
+                let result = self.tree_builder.process_token(token, line_number);
+                self.recorder().start_tag_processed(self_closing);
+                result
+            }
+            TagKind::EndTag => {
+                let name = tag.name.clone();
+                self.recorder().end_tag(&name, start, end);
+                self.tree_builder.process_token(token, line_number)
+            }
+        }
+    }
+
+    fn end(&mut self) {
+        self.tree_builder.end();
+        let len = self.source.len();
+        self.recorder().input_ended(len);
+    }
+
+    fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+        self.tree_builder
+            .adjusted_current_node_present_but_not_in_html_namespace()
+    }
+}
+
+/// Find the next tag of this kind and name at or after `from`, returning its byte range
+///
+/// Comments, doctypes and CDATA sections are skipped. In raw text only the end tag is
+/// looked for, since the contents of e.g. `<script>` may contain anything.
+pub(crate) fn find_tag(
+    source: &str,
+    from: usize,
+    kind: TagKind,
+    name: &str,
+    raw_text: bool,
+) -> Option<(usize, usize)> {
+    let bytes = source.as_bytes();
+    let prefix = match kind {
+        TagKind::StartTag => "<",
+        TagKind::EndTag => "</",
+    };
+    let mut pos = from;
+
+    while let Some(found) = source.get(pos..)?.find('<') {
+        let start = pos + found;
+        let rest = &source[start..];
+
+        if !raw_text {
+            let skip_to = if let Some(comment) = rest.strip_prefix("<!--") {
+                Some(comment.find("-->").map(|idx| start + 4 + idx + 3))
+            } else if rest.starts_with("<![CDATA[") {
+                Some(rest.find("]]>").map(|idx| start + idx + 3))
+            } else if rest.starts_with("<!") || rest.starts_with("<?") {
+                Some(rest.find('>').map(|idx| start + idx + 1))
+            } else {
+                None
+            };
+            if let Some(skip_to) = skip_to {
+                pos = skip_to?;
+                continue;
+            }
+        }
+
+        let name_start = start + prefix.len();
+        let name_end = name_start + name.len();
+        let is_match = rest.starts_with(prefix)
+            && source
+                .get(name_start..name_end)
+                .is_some_and(|candidate| candidate.eq_ignore_ascii_case(name))
+            && bytes
+                .get(name_end)
+                .is_none_or(|&b| b.is_ascii_whitespace() || b == b'/' || b == b'>');
+        if is_match {
+            return Some((start, tag_end(source, name_end)));
+        }
+        pos = start + 1;
+    }
+
+    None
+}
+
+/// Offset just past the `>` closing a tag whose attributes start at `from`
+fn tag_end(source: &str, from: usize) -> usize {
+    let bytes = source.as_bytes();
+    let mut pos = from;
+    let mut after_equals = false;
+
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'>' => return pos + 1,
+            quote @ (b'"' | b'\'') if after_equals => {
+                match source[pos + 1..].find(quote as char) {
+                    Some(idx) => pos += idx + 1,
+                    None => return source.len(),
+                }
+                after_equals = false;
+            }
+            b'=' => after_equals = true,
+            b if b.is_ascii_whitespace() => {}
+            _ => after_equals = false,
+        }
+        pos += 1;
+    }
+
+    source.len()
+}
+
+/// Parse an HTML document or fragment like [`crate::parse_document`], recording where
+/// each element is in the source
+///
+/// Spans are approximate: the tokenizer doesn't report positions, so each tag is found by
+/// scanning the source for the next tag of its name after the previous one. Markup the
+/// tokenizer reads differently than that scan, such as tags inside malformed comments or
+/// attribute values with unbalanced quotes, can shift the spans that follow.
+pub fn parse_with_spans<'a>(
+    html: &'a str,
+    options: &QueryOptions,
+) -> Result<(NodeRef, SourceMap<'a>), Box<dyn Error>> {
+    if options.parser == ParserKind::Xml {
+        return Err("Source positions are only tracked by the HTML parser".into());
+    }
+
+    let mut sink = NodeSink::default();
+    sink.spans = Some(SpanRecorder::default());
+    let (tree_builder, initial_state) = match &options.context {
+        Some(context) => {
+            let context = create_element(&mut sink, crate::context_name(context), Vec::new());
+            let tree_builder =
+                TreeBuilder::new_for_fragment(sink, context, None, TreeBuilderOpts::default());
+            let state = tree_builder.tokenizer_state_for_context_elem();
+            (tree_builder, Some(state))
+        }
+        None => (TreeBuilder::new(sink, TreeBuilderOpts::default()), None),
+    };
+    let observer = SpanObserver {
+        tree_builder,
+        source: html,
+        cursor: 0,
+        raw_text: None,
+    };
+    let opts = TokenizerOpts {
+        initial_state,
+        ..Default::default()
+    };
+    let mut tokenizer = Tokenizer::new(observer, opts);
+
+    let mut input = BufferQueue::new();
+    input.push_back(StrTendril::from(html));
+    while let TokenizerResult::Script(_) = tokenizer.feed(&mut input) {}
+    tokenizer.end();
+
+    let sink = tokenizer.sink.tree_builder.sink;
+    let recorder = sink.spans.expect("observed sinks record spans");
+    if options.templates {
+        template::inline_templates(&sink.document);
+    }
+
+    let map = SourceMap::new(html, recorder.elements, &sink.document);
+    Ok((sink.document, map))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(html: &str, selector: &str) -> Vec<String> {
+        let (document, map) = parse_with_spans(html, &QueryOptions::default()).unwrap();
+        document
+            .select(selector)
+            .unwrap()
+            .map(|element| {
+                map.source_html(element.as_node())
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_source_html_is_unnormalized() {
+        let html = "<DIV Class='job'  data-x=\"a>b\">Dev &amp; Ops<BR></DIV>";
+        assert_eq!(sources(html, "div"), vec![html]);
+        assert_eq!(sources(html, "br"), vec!["<BR>"]);
+    }
+
+    #[test]
+    fn test_implied_end_tags() {
+        let html = "<ul>\n<li>One\n<li>Two</ul>";
+        assert_eq!(sources(html, "li"), vec!["<li>One\n", "<li>Two"]);
+        assert_eq!(sources("<p>Unclosed", "p"), vec!["<p>Unclosed"]);
+    }
+
+    #[test]
+    fn test_tags_in_comments_and_scripts_are_skipped() {
+        let html = "<!-- <p>no</p> --><script>x = '<p>no</p>';</script><p>yes</p>";
+        assert_eq!(sources(html, "p"), vec!["<p>yes</p>"]);
+        assert_eq!(
+            sources(html, "script"),
+            vec!["<script>x = '<p>no</p>';</script>"]
+        );
+    }
+
+    #[test]
+    fn test_implied_elements_span_their_children() {
+        let html = "<table><tr><td>1</td></tr></table>";
+        assert_eq!(sources(html, "tbody"), vec!["<tr><td>1</td></tr>"]);
+    }
+
+    #[test]
+    fn test_span_lines_and_columns() {
+        let html = "<body>\n  <p>é <b>x</b></p>";
+        let (document, map) = parse_with_spans(html, &QueryOptions::default()).unwrap();
+        let b = document.select_first("b").unwrap();
+        let span = map.span(b.as_node()).unwrap();
+        assert_eq!(
+            span,
+            SourceSpan {
+                offset: 15,
+                length: 8,
+                line: 2,
+                column: 8
+            }
+        );
+        assert_eq!(
+            span.to_json(),
+            r#"{"offset":15,"length":8,"line":2,"column":8}"#
+        );
+    }
+
+    #[test]
+    fn test_fragment_context() {
+        let options = QueryOptions::parse("context=tr").unwrap();
+        let (document, map) = parse_with_spans("<td>a<td>b</td>", &options).unwrap();
+        let cells: Vec<&str> = document
+            .select("td")
+            .unwrap()
+            .filter_map(|td| map.source_html(td.as_node()))
+            .collect();
+        assert_eq!(cells, vec!["<td>a", "<td>b</td>"]);
+    }
+
+    #[test]
+    fn test_many_siblings() {
+        // Thousands of siblings, each needing the start of the next to find its end
+        let html = "<div><p>x</p></div>".repeat(5000);
+        let paragraphs = sources(&html, "p");
+        assert_eq!(paragraphs.len(), 5000);
+        assert!(paragraphs.iter().all(|p| p == "<p>x</p>"));
+    }
+
+    #[test]
+    fn test_xml_parser_is_rejected() {
+        assert!(parse_with_spans("<a/>", &QueryOptions::xml()).is_err());
+    }
+}
@@ -0,0 +1,519 @@
+use crate::{attribute, collapse_whitespace, parse_document, selector, QueryOptions};
+use html5ever::local_name;
+use kuchikiki::NodeRef;
+use std::error::Error;
+
+/// Largest `colspan` browsers honor
+const MAX_COLSPAN: usize = 1000;
+/// Largest `rowspan` browsers honor
+const MAX_ROWSPAN: usize = 65534;
+
+/// How the column names of a table are found
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum HeaderMode {
+    /// `<thead>` rows, or leading rows of `<th>` cells, with key/value tables detected
+    #[default]
+    Auto,
+    /// The first row, whatever its cells are
+    FirstRow,
+    /// No header, columns are named `col1`, `col2`, ...
+    None,
+    /// The first column holds the keys and each further column is a record
+    Vertical,
+}
+
+impl HeaderMode {
+    pub fn parse(mode: &str) -> Result<Self, Box<dyn Error>> {
+        match mode.trim().to_ascii_lowercase().as_str() {
+            "" | "auto" => Ok(HeaderMode::Auto),
+            "first_row" | "first-row" | "row" => Ok(HeaderMode::FirstRow),
+            "none" => Ok(HeaderMode::None),
+            "vertical" => Ok(HeaderMode::Vertical),
+            other => Err(format!(
+                "Unknown header mode '{}', expected 'auto', 'first_row', 'none' or 'vertical'",
+                other
+            )
+            .into()),
+        }
+    }
+}
+
+/// A data row of a table, with cells spanning several columns repeated in each of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow {
+    /// Cell text by column name
+    pub cells: Vec<(String, String)>,
+    /// First link of each cell that has one, by column name
+    pub links: Vec<(String, String)>,
+}
+
+/// A table converted to named columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub id: Option<String>,
+    pub caption: Option<String>,
+    pub columns: Vec<String>,
+    pub rows: Vec<TableRow>,
+    /// Keys in the first column rather than a header row
+    pub is_vertical: bool,
+    /// Inside a cell of another table
+    pub is_nested: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Cell {
+    text: String,
+    link: Option<String>,
+    is_header: bool,
+}
+
+fn is_element(node: &NodeRef, name: &str) -> bool {
+    node.as_element()
+        .is_some_and(|element| &*element.name.local == name)
+}
+
+/// Rows of the table itself, not of tables nested in its cells, with whether they are in
+/// the `<thead>`
+fn table_rows(table: &NodeRef) -> Vec<(NodeRef, bool)> {
+    let mut rows = Vec::new();
+    for child in table.children() {
+        if is_element(&child, "tr") {
+            rows.push((child, false));
+        } else if ["thead", "tbody", "tfoot"]
+            .iter()
+            .any(|section| is_element(&child, section))
+        {
+            let in_head = is_element(&child, "thead");
+            rows.extend(
+                child
+                    .children()
+                    .filter(|row| is_element(row, "tr"))
+                    .map(|row| (row, in_head)),
+            );
+        }
+    }
+    rows
+}
+
+fn span(cell: &NodeRef, name: &str, max: usize) -> usize {
+    attribute(cell, name)
+        .and_then(|value| value.trim().parse::<usize>().ok())
+        .unwrap_or(1)
+        .clamp(1, max)
+}
+
+/// Index past the last row of the section (`<thead>`, `<tbody>`, `<tfoot>` or the rows
+/// directly in the table) each row is in
+fn section_ends(rows: &[(NodeRef, bool)]) -> Vec<usize> {
+    let mut ends = vec![rows.len(); rows.len()];
+    for r in (0..rows.len().saturating_sub(1)).rev() {
+        ends[r] = if rows[r].0.parent() == rows[r + 1].0.parent() {
+            ends[r + 1]
+        } else {
+            r + 1
+        };
+    }
+    ends
+}
+
+/// Lay the cells out on a grid, repeating cells that span several rows or columns
+fn grid(rows: &[(NodeRef, bool)]) -> Vec<Vec<Cell>> {
+    let mut grid: Vec<Vec<Option<Cell>>> = vec![Vec::new(); rows.len()];
+    let section_ends = section_ends(rows);
+
+    for (r, (row, _)) in rows.iter().enumerate() {
+        let mut col = 0;
+        for cell_node in row
+            .children()
+            .filter(|cell| is_element(cell, "td") || is_element(cell, "th"))
+        {
+            while grid[r].get(col).is_some_and(Option::is_some) {
+                col += 1;
+            }
+            let cell = Cell {
+                text: collapse_whitespace(&cell_node.text_contents()),
+                link: cell_node.select_first("a[href]").ok().and_then(|link| {
+                    link.attributes
+                        .borrow()
+                        .get(local_name!("href"))
+                        .map(String::from)
+                }),
+                is_header: is_element(&cell_node, "th"),
+            };
+            let colspan = span(&cell_node, "colspan", MAX_COLSPAN);
+            // `rowspan="0"` spans the rest of the section
+            let rowspan = if attribute(&cell_node, "rowspan")
+                .is_some_and(|value| value.trim().parse::<usize>() == Ok(0))
+            {
+                section_ends[r] - r
+            } else {
+                span(&cell_node, "rowspan", MAX_ROWSPAN).min(rows.len() - r)
+            };
+
+            for spanned in &mut grid[r..r + rowspan] {
+                if spanned.len() < col + colspan {
+                    spanned.resize(col + colspan, None);
+                }
+                for slot in &mut spanned[col..col + colspan] {
+                    *slot = Some(cell.clone());
+                }
+            }
+            col += colspan;
+        }
+    }
+
+    let width = grid.iter().map(Vec::len).max().unwrap_or(0);
+    grid.into_iter()
+        .map(|row| {
+            let mut row: Vec<Cell> = row.into_iter().map(Option::unwrap_or_default).collect();
+            row.resize(width, Cell::default());
+            row
+        })
+        .collect()
+}
+
+/// Make names unique by numbering repeats (`Name`, `Name_2`, ...), and name empty ones
+/// after their position
+fn unique_names(names: impl IntoIterator<Item = String>, fallback: &str) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for (idx, name) in names.into_iter().enumerate() {
+        let name = if name.is_empty() {
+            format!("{}{}", fallback, idx + 1)
+        } else {
+            name
+        };
+        let mut candidate = name.clone();
+        let mut n = 2;
+        while unique.contains(&candidate) {
+            candidate = format!("{}_{}", name, n);
+            n += 1;
+        }
+        unique.push(candidate);
+    }
+    unique
+}
+
+/// Key/value tables have a label in the first column of every row: a `<th>` next to
+/// `<td>`s, or text ending in a colon in two-column tables
+fn looks_vertical(grid: &[Vec<Cell>]) -> bool {
+    let width = grid.first().map_or(0, Vec::len);
+    if width < 2 || grid.is_empty() {
+        return false;
+    }
+    let header_keys = grid
+        .iter()
+        .all(|row| row[0].is_header && row[1..].iter().any(|cell| !cell.is_header));
+    let colon_keys = width == 2 && grid.iter().all(|row| row[0].text.ends_with(':'));
+    header_keys || colon_keys
+}
+
+fn record(columns: &[String], cells: impl Iterator<Item = Cell>) -> TableRow {
+    let mut row = TableRow {
+        cells: Vec::new(),
+        links: Vec::new(),
+    };
+    for (column, cell) in columns.iter().zip(cells) {
+        if let Some(link) = cell.link {
+            row.links.push((column.clone(), link));
+        }
+        row.cells.push((column.clone(), cell.text));
+    }
+    row
+}
+
+/// Convert a `<table>` element into rows with named columns
+pub fn parse_table(table: &NodeRef, header: HeaderMode) -> Table {
+    let rows = table_rows(table);
+    // Empty rows are dropped, and only the <thead> rows left count towards the header
+    let (grid, heads): (Vec<Vec<Cell>>, Vec<bool>) = grid(&rows)
+        .into_iter()
+        .zip(rows.iter().map(|(_, in_head)| *in_head))
+        .filter(|(row, _)| row.iter().any(|cell| !cell.text.is_empty()))
+        .unzip();
+    let in_head = heads.iter().filter(|in_head| **in_head).count();
+
+    let header_rows = match header {
+        HeaderMode::None | HeaderMode::Vertical => 0,
+        HeaderMode::FirstRow => grid.len().min(1),
+        HeaderMode::Auto if in_head > 0 => in_head.min(grid.len()),
+        HeaderMode::Auto => {
+            let leading = grid
+                .iter()
+                .take_while(|row| row.iter().all(|cell| cell.is_header))
+                .count();
+            // In a table of only <th>s, the first row is the header and the others are data
+            if leading == grid.len() {
+                leading.min(1)
+            } else {
+                leading
+            }
+        }
+    };
+    let is_vertical = match header {
+        HeaderMode::Vertical => true,
+        HeaderMode::Auto => header_rows == 0 && looks_vertical(&grid),
+        _ => false,
+    };
+
+    let (columns, records) = if is_vertical {
+        let keys = unique_names(
+            grid.iter().map(|row| {
+                row.first().map_or(String::new(), |cell| {
+                    cell.text.trim_end_matches(':').trim_end().to_string()
+                })
+            }),
+            "row",
+        );
+        let width = grid.first().map_or(0, Vec::len);
+        let records = (1..width)
+            .map(|col| record(&keys, grid.iter().map(|row| row[col].clone())))
+            .filter(|row| row.cells.iter().any(|(_, text)| !text.is_empty()))
+            .collect();
+        (keys, records)
+    } else {
+        let width = grid.first().map_or(0, Vec::len);
+        let names = (0..width).map(|col| {
+            let mut parts: Vec<&str> = Vec::new();
+            for row in &grid[..header_rows] {
+                let text = row[col].text.as_str();
+                if !text.is_empty() && parts.last() != Some(&text) {
+                    parts.push(text);
+                }
+            }
+            parts.join(" / ")
+        });
+        let columns = unique_names(names, "col");
+        let records = grid[header_rows..]
+            .iter()
+            .map(|row| record(&columns, row.iter().cloned()))
+            .collect();
+        (columns, records)
+    };
+
+    Table {
+        id: attribute(table, "id").filter(|id| !id.is_empty()),
+        caption: table
+            .children()
+            .find(|child| is_element(child, "caption"))
+            .map(|caption| collapse_whitespace(&caption.text_contents()))
+            .filter(|caption| !caption.is_empty()),
+        columns,
+        rows: records,
+        is_vertical,
+        is_nested: table
+            .ancestors()
+            .any(|ancestor| is_element(&ancestor, "table")),
+    }
+}
+
+/// Convert the table matched by `selector` (or the first table inside the matched
+/// element) into rows, `None` if there is no such table
+pub fn html_table(
+    html: &str,
+    selector: &str,
+    header: HeaderMode,
+) -> Result<Option<Table>, Box<dyn Error>> {
+    let options = QueryOptions::default();
+    let document = parse_document(html, &options);
+    let table = selector::select_nodes(&document, selector, &options)?
+        .into_iter()
+        .find_map(|node| {
+            if is_element(&node, "table") {
+                Some(node)
+            } else {
+                node.select_first("table")
+                    .ok()
+                    .map(|table| table.as_node().clone())
+            }
+        });
+    Ok(table.map(|table| parse_table(&table, header)))
+}
+
+/// Every table of a document in document order, with automatically detected headers
+pub fn html_tables(html: &str) -> Vec<Table> {
+    let document = parse_document(html, &QueryOptions::default());
+    document
+        .select("table")
+        .map(|tables| {
+            tables
+                .map(|table| parse_table(table.as_node(), HeaderMode::Auto))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(row: &TableRow) -> Vec<(&str, &str)> {
+        row.cells
+            .iter()
+            .map(|(name, text)| (name.as_str(), text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_header_from_thead() {
+        let table = html_table(
+            "<table><thead><tr><th>Title</th><th>Salary</th></tr></thead>
+             <tbody><tr><td><a href='/jobs/1'>Dev</a></td><td>50k</td></tr>
+             <tr><td>Ops</td><td>45k</td></tr></tbody></table>",
+            "table",
+            HeaderMode::Auto,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(table.columns, vec!["Title", "Salary"]);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(
+            cells(&table.rows[0]),
+            vec![("Title", "Dev"), ("Salary", "50k")]
+        );
+        assert_eq!(
+            table.rows[0].links,
+            vec![("Title".to_string(), "/jobs/1".to_string())]
+        );
+        assert!(!table.is_vertical);
+    }
+
+    #[test]
+    fn test_spans_are_expanded() {
+        let table = html_table(
+            "<table><tr><th rowspan=2>Role</th><th colspan=2>Salary</th></tr>
+             <tr><th>Min</th><th>Max</th></tr>
+             <tr><td rowspan=2>Dev</td><td>40</td><td>60</td></tr>
+             <tr><td colspan=2>n/a</td></tr></table>",
+            "table",
+            HeaderMode::Auto,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(table.columns, vec!["Role", "Salary / Min", "Salary / Max"]);
+        assert_eq!(
+            cells(&table.rows[1]),
+            vec![
+                ("Role", "Dev"),
+                ("Salary / Min", "n/a"),
+                ("Salary / Max", "n/a")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_empty_head_rows() {
+        let table = html_table(
+            "<table><thead><tr><th>Role</th><th>City</th></tr><tr></tr></thead>
+             <tbody><tr><td>Dev</td><td>Berlin</td></tr></tbody></table>",
+            "table",
+            HeaderMode::Auto,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(table.columns, vec!["Role", "City"]);
+        assert_eq!(
+            table.rows.iter().map(cells).collect::<Vec<_>>(),
+            vec![vec![("Role", "Dev"), ("City", "Berlin")]]
+        );
+    }
+
+    #[test]
+    fn test_rowspan_zero_spans_the_section() {
+        let table = html_table(
+            "<table><thead><tr><th>Team</th><th>Role</th></tr></thead>
+             <tbody><tr><td rowspan=0>Core</td><td>Dev</td></tr>
+             <tr><td>Ops</td></tr><tr><td>QA</td></tr></tbody>
+             <tfoot><tr><td>Total</td><td>3</td></tr></tfoot></table>",
+            "table",
+            HeaderMode::Auto,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            table.rows.iter().map(cells).collect::<Vec<_>>(),
+            vec![
+                vec![("Team", "Core"), ("Role", "Dev")],
+                vec![("Team", "Core"), ("Role", "Ops")],
+                vec![("Team", "Core"), ("Role", "QA")],
+                vec![("Team", "Total"), ("Role", "3")],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vertical_tables() {
+        let html = "<table id=facts><caption>Job facts</caption>
+            <tr><th>Location</th><td>Berlin</td></tr>
+            <tr><th>Salary</th><td>60k</td></tr></table>";
+        let table = html_table(html, "#facts", HeaderMode::Auto)
+            .unwrap()
+            .unwrap();
+        assert!(table.is_vertical);
+        assert_eq!(table.columns, vec!["Location", "Salary"]);
+        assert_eq!(
+            cells(&table.rows[0]),
+            vec![("Location", "Berlin"), ("Salary", "60k")]
+        );
+        assert_eq!(table.caption.as_deref(), Some("Job facts"));
+
+        let colons = "<table><tr><td>Team:</td><td>Data</td></tr><tr><td>Level:</td><td>Senior</td></tr></table>";
+        let table = html_table(colons, "table", HeaderMode::Auto)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cells(&table.rows[0]),
+            vec![("Team", "Data"), ("Level", "Senior")]
+        );
+    }
+
+    #[test]
+    fn test_header_modes() {
+        let html = "<table><tr><td>a</td><td>b</td></tr><tr><td>1</td><td></td></tr></table>";
+        let none = html_table(html, "table", HeaderMode::None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(none.columns, vec!["col1", "col2"]);
+        assert_eq!(none.rows.len(), 2);
+
+        let first = html_table(html, "table", HeaderMode::FirstRow)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.columns, vec!["a", "b"]);
+        assert_eq!(cells(&first.rows[0]), vec![("a", "1"), ("b", "")]);
+
+        assert!(HeaderMode::parse("sideways").is_err());
+    }
+
+    #[test]
+    fn test_duplicate_and_empty_header_names() {
+        let table = html_table(
+            "<table><tr><th></th><th>Name</th><th>Name</th></tr><tr><td>1</td><td>a</td><td>b</td></tr></table>",
+            "table",
+            HeaderMode::Auto,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(table.columns, vec!["col1", "Name", "Name_2"]);
+    }
+
+    #[test]
+    fn test_nested_tables_and_overview() {
+        let html = "<div class=grid><table><tr><th>Outer</th></tr><tr><td>
+            <table><tr><td>Inner:</td><td>x</td></tr></table></td></tr></table></div>";
+        let table = html_table(html, ".grid", HeaderMode::Auto)
+            .unwrap()
+            .unwrap();
+        assert_eq!(table.columns, vec!["Outer"]);
+        assert_eq!(table.rows.len(), 1);
+
+        let tables = html_tables(html);
+        assert_eq!(tables.len(), 2);
+        assert!(!tables[0].is_nested);
+        assert!(tables[1].is_nested);
+        assert!(tables[1].is_vertical);
+        assert_eq!(
+            html_table("<p>No tables</p>", "table", HeaderMode::Auto).unwrap(),
+            None
+        );
+    }
+}
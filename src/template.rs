@@ -0,0 +1,96 @@
+use html5ever::local_name;
+use kuchikiki::NodeRef;
+
+fn is_template(node: &NodeRef) -> bool {
+    node.as_element()
+        .map(|elem| elem.name.local == local_name!("template"))
+        .unwrap_or(false)
+}
+
+/// Move the contents of every `<template>` (including declarative shadow roots) into the
+/// template element itself, so that regular selectors can match inside them.
+///
+/// html5ever keeps template contents in a separate document fragment that is not part of
+/// the tree, which makes them invisible to `select`.
+pub fn inline_templates(node: &NodeRef) {
+    let mut pending: Vec<NodeRef> = node.inclusive_descendants().filter(is_template).collect();
+
+    while let Some(template) = pending.pop() {
+        let Some(contents) = template
+            .as_element()
+            .and_then(|elem| elem.template_contents.clone())
+        else {
+            continue;
+        };
+
+        for child in contents.children().collect::<Vec<_>>() {
+            // Nested templates only become reachable once their parent has been inlined
+            pending.extend(child.inclusive_descendants().filter(is_template));
+            template.append(child);
+        }
+    }
+}
+
+/// Roots to continue matching in after a `>>>` deep combinator
+///
+/// Returns the contents of every template and shadow root at or below `node`. Templates
+/// that were already inlined by [`inline_templates`] are returned as the element itself.
+pub fn template_roots(node: &NodeRef) -> Vec<NodeRef> {
+    node.inclusive_descendants()
+        .filter(is_template)
+        .map(|template| {
+            template
+                .as_element()
+                .and_then(|elem| elem.template_contents.clone())
+                .filter(|contents| contents.first_child().is_some())
+                .unwrap_or(template)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    const SHADOW_HTML: &str = r#"<html><body><x-card><template shadowrootmode="open"><h2>Inside</h2><template><p>Nested</p></template></template></x-card></body></html>"#;
+
+    #[test]
+    fn test_template_contents_hidden_by_default() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        assert!(doc.select_first("h2").is_err());
+    }
+
+    #[test]
+    fn test_inline_templates() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        inline_templates(&doc);
+        assert_eq!(
+            doc.select_first("x-card h2").unwrap().text_contents(),
+            "Inside"
+        );
+        assert_eq!(
+            doc.select_first("template p").unwrap().text_contents(),
+            "Nested"
+        );
+    }
+
+    #[test]
+    fn test_template_roots() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        let host = doc.select_first("x-card").unwrap();
+        let roots = template_roots(host.as_node());
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].to_string(), "<h2>Inside</h2><template></template>");
+    }
+
+    #[test]
+    fn test_template_roots_after_inlining() {
+        let doc = kuchikiki::parse_html().one(SHADOW_HTML);
+        inline_templates(&doc);
+        let host = doc.select_first("x-card").unwrap();
+        let roots = template_roots(host.as_node());
+        assert_eq!(roots.len(), 2);
+        assert!(roots.iter().all(|root| root.as_element().is_some()));
+    }
+}
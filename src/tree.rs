@@ -0,0 +1,211 @@
+use crate::collapse_whitespace;
+use kuchikiki::iter::NodeEdge;
+use kuchikiki::{NodeData, NodeRef};
+
+/// Kind of a node in [`dom_table`] output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeType {
+    Element,
+    Text,
+    Comment,
+}
+
+impl NodeType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeType::Element => "element",
+            NodeType::Text => "text",
+            NodeType::Comment => "comment",
+        }
+    }
+}
+
+/// One row of the DOM as a table
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeNode {
+    /// 1-based position in document order
+    pub node_id: i64,
+    /// NULL for top-level nodes
+    pub parent_id: Option<i64>,
+    pub prev_sibling_id: Option<i64>,
+    /// 0 for top-level nodes
+    pub depth: i64,
+    pub node_type: NodeType,
+    /// Element name, NULL for text and comments
+    pub tag: Option<String>,
+    /// Attributes in source order, names of namespaced ones with their prefix (`xlink:href`)
+    pub attributes: Vec<(String, String)>,
+    /// Whitespace-collapsed text of the node itself, or of an element's direct text children
+    pub own_text: Option<String>,
+    /// 0-based position among the parent's rows
+    pub child_index: i64,
+}
+
+/// Whether a node gets a row: elements, comments and text that isn't only whitespace
+fn node_type(node: &NodeRef) -> Option<NodeType> {
+    match node.data() {
+        NodeData::Element(_) => Some(NodeType::Element),
+        NodeData::Comment(_) => Some(NodeType::Comment),
+        NodeData::Text(text) if !text.borrow().trim().is_empty() => Some(NodeType::Text),
+        _ => None,
+    }
+}
+
+fn own_text(node: &NodeRef) -> Option<String> {
+    let text = match node.data() {
+        NodeData::Element(_) => node
+            .children()
+            .filter_map(|child| child.as_text().map(|text| text.borrow().clone()))
+            .collect::<Vec<_>>()
+            .join(" "),
+        NodeData::Text(text) | NodeData::Comment(text) => text.borrow().clone(),
+        _ => return None,
+    };
+    Some(collapse_whitespace(&text)).filter(|text| !text.is_empty())
+}
+
+fn attributes(node: &NodeRef) -> Vec<(String, String)> {
+    let Some(element) = node.as_element() else {
+        return Vec::new();
+    };
+    element
+        .attributes
+        .borrow()
+        .map
+        .iter()
+        .map(|(name, attr)| {
+            let name = match &attr.prefix {
+                Some(prefix) => format!("{}:{}", prefix, name.local),
+                None => name.local.to_string(),
+            };
+            (name, attr.value.clone())
+        })
+        .collect()
+}
+
+/// Flatten the tree below `root` into rows, one per element, comment and non-blank text
+/// node, in document order
+///
+/// Doctypes and whitespace-only text are left out, and don't count for `child_index` or
+/// `prev_sibling_id`.
+pub fn dom_table(root: &NodeRef) -> Vec<TreeNode> {
+    let mut rows: Vec<TreeNode> = Vec::new();
+    // Row index of each open ancestor with a row, and of its last child row
+    let mut ancestors: Vec<(usize, Option<usize>)> = Vec::new();
+    let mut top_level_last: Option<usize> = None;
+
+    for edge in root.traverse() {
+        match edge {
+            NodeEdge::Start(node) => {
+                let Some(node_type) = node_type(&node) else {
+                    continue;
+                };
+                let idx = rows.len();
+                let (parent_id, prev) = match ancestors.last_mut() {
+                    Some((parent, last_child)) => {
+                        (Some(rows[*parent].node_id), last_child.replace(idx))
+                    }
+                    None => (None, top_level_last.replace(idx)),
+                };
+                rows.push(TreeNode {
+                    node_id: idx as i64 + 1,
+                    parent_id,
+                    prev_sibling_id: prev.map(|prev| rows[prev].node_id),
+                    depth: ancestors.len() as i64,
+                    node_type,
+                    tag: node
+                        .as_element()
+                        .map(|element| element.name.local.to_string()),
+                    attributes: attributes(&node),
+                    own_text: own_text(&node),
+                    child_index: prev.map_or(0, |prev| rows[prev].child_index + 1),
+                });
+                if node_type == NodeType::Element {
+                    ancestors.push((idx, None));
+                }
+            }
+            NodeEdge::End(node) => {
+                if node.as_element().is_some() {
+                    ancestors.pop();
+                }
+            }
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kuchikiki::traits::TendrilSink;
+
+    /// node_id, parent_id, prev_sibling_id, depth, node_type, tag, child_index
+    type Shape<'a> = (
+        i64,
+        Option<i64>,
+        Option<i64>,
+        i64,
+        &'a str,
+        Option<&'a str>,
+        i64,
+    );
+
+    #[test]
+    fn test_dom_table() {
+        let document = kuchikiki::parse_html().one(
+            "<!DOCTYPE html><html><head></head><body>\n<ul class='jobs'>\n  <li>Dev <b>Ops</b> team</li>\n  <!-- hiring --></ul></body></html>",
+        );
+        let rows = dom_table(&document);
+        let summary: Vec<Shape> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.node_id,
+                    row.parent_id,
+                    row.prev_sibling_id,
+                    row.depth,
+                    row.node_type.name(),
+                    row.tag.as_deref(),
+                    row.child_index,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, None, None, 0, "element", Some("html"), 0),
+                (2, Some(1), None, 1, "element", Some("head"), 0),
+                (3, Some(1), Some(2), 1, "element", Some("body"), 1),
+                (4, Some(3), None, 2, "element", Some("ul"), 0),
+                (5, Some(4), None, 3, "element", Some("li"), 0),
+                (6, Some(5), None, 4, "text", None, 0),
+                (7, Some(5), Some(6), 4, "element", Some("b"), 1),
+                (8, Some(7), None, 5, "text", None, 0),
+                (9, Some(5), Some(7), 4, "text", None, 2),
+                (10, Some(4), Some(5), 3, "comment", None, 1),
+            ]
+        );
+        assert_eq!(
+            rows[3].attributes,
+            vec![("class".to_string(), "jobs".to_string())]
+        );
+        assert_eq!(rows[4].own_text.as_deref(), Some("Dev team"));
+        assert_eq!(rows[9].own_text.as_deref(), Some("hiring"));
+        assert_eq!(rows[2].own_text, None);
+    }
+
+    #[test]
+    fn test_dom_table_prefixed_attributes() {
+        let document = kuchikiki::parse_html().one("<svg><use xlink:href='#icon'></use></svg>");
+        let rows = dom_table(&document);
+        let used = rows
+            .iter()
+            .find(|row| row.tag.as_deref() == Some("use"))
+            .unwrap();
+        assert_eq!(
+            used.attributes,
+            vec![("xlink:href".to_string(), "#icon".to_string())]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+use crate::sink::NodeSink;
+use crate::source::{find_tag, RAW_TEXT_ELEMENTS};
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::{
+    BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+    TokenizerResult,
+};
+use html5ever::tree_builder::{QuirksMode, TreeBuilder, TreeBuilderOpts};
+use html5ever::{local_name, LocalName};
+use kuchikiki::NodeRef;
+use std::collections::HashMap;
+
+/// Elements without an end tag
+pub(crate) const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "basefont", "bgsound", "br", "col", "embed", "frame", "hr", "img", "input",
+    "keygen", "link", "meta", "param", "source", "track", "wbr",
+];
+
+/// Elements whose end tag may be omitted
+const OPTIONAL_END_TAGS: &[&str] = &[
+    "html", "head", "body", "p", "li", "dt", "dd", "rt", "rp", "optgroup", "option", "colgroup",
+    "caption", "thead", "tbody", "tfoot", "tr", "td", "th",
+];
+
+/// A problem found while parsing a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationMessage {
+    pub line: u64,
+    /// 1-based column, in characters, of the tag the message is about. The tokenizer doesn't
+    /// report positions, so messages not tied to a tag, like most tokenizer errors, have none.
+    pub col: Option<u64>,
+    pub message: String,
+}
+
+/// Parse errors and conformance flags of an HTML document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    /// Parse errors, duplicate IDs and unclosed elements, ordered by line and column
+    pub messages: Vec<ValidationMessage>,
+    pub quirks_mode: QuirksMode,
+    /// Name of the doctype (`html` for `<!DOCTYPE html>`), if any
+    pub doctype: Option<String>,
+    pub duplicate_ids: Vec<String>,
+    /// Elements that were implicitly closed or never closed, excluding those with
+    /// optional end tags like `<p>` or `<li>`
+    pub unclosed_elements: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn quirks_mode_name(&self) -> &'static str {
+        match self.quirks_mode {
+            QuirksMode::Quirks => "quirks",
+            QuirksMode::LimitedQuirks => "limited-quirks",
+            QuirksMode::NoQuirks => "no-quirks",
+        }
+    }
+}
+
+/// Watches the tag tokens on their way into the tree builder, to report issues the
+/// HTML parser recovers from silently
+struct TagObserver<'a> {
+    tree_builder: TreeBuilder<NodeRef, NodeSink>,
+    source: &'a str,
+    /// Where the search for the next tag starts, as in [`crate::source`]
+    cursor: usize,
+    /// Raw text element the last start tag opened
+    raw_text: Option<LocalName>,
+    /// Column of each tree builder error, for those reported on a tag
+    error_cols: Vec<Option<u64>>,
+    open_elements: Vec<(LocalName, u64, Option<u64>)>,
+    ids: HashMap<String, u64>,
+    messages: Vec<ValidationMessage>,
+    duplicate_ids: Vec<String>,
+    unclosed_elements: Vec<String>,
+}
+
+impl TagObserver<'_> {
+    /// Column of a tag token, found by scanning the source for it after the previous one
+    fn locate(&mut self, tag: &Tag) -> Option<u64> {
+        let raw_text = self.raw_text.take().filter(|name| *name == tag.name);
+        let (start, end) = find_tag(
+            self.source,
+            self.cursor,
+            tag.kind,
+            &tag.name,
+            raw_text.is_some(),
+        )?;
+        self.cursor = end;
+        if tag.kind == TagKind::StartTag && RAW_TEXT_ELEMENTS.contains(&&*tag.name) {
+            self.raw_text = Some(tag.name.clone());
+        }
+        let line_start = self.source[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        Some(self.source[line_start..start].chars().count() as u64 + 1)
+    }
+
+    fn observe(&mut self, tag: &Tag, line: u64, col: Option<u64>) {
+        match tag.kind {
+            TagKind::StartTag => {
+                let id = tag
+                    .attrs
+                    .iter()
+                    .find(|attr| attr.name.local == local_name!("id"))
+                    .map(|attr| attr.value.to_string());
+                if let Some(id) = id.filter(|id| !id.is_empty()) {
+                    if let Some(first) = self.ids.get(&id) {
+                        self.report(
+                            line,
+                            col,
+                            format!("Duplicate id '{}', first used on line {}", id, first),
+                        );
+                        if !self.duplicate_ids.contains(&id) {
+                            self.duplicate_ids.push(id);
+                        }
+                    } else {
+                        self.ids.insert(id, line);
+                    }
+                }
+
+                if !tag.self_closing && !VOID_ELEMENTS.contains(&&*tag.name) {
+                    self.open_elements.push((tag.name.clone(), line, col));
+                }
+            }
+            TagKind::EndTag => {
+                // Stray end tags are reported by the tree builder
+                if let Some(idx) = self
+                    .open_elements
+                    .iter()
+                    .rposition(|(name, _, _)| *name == tag.name)
+                {
+                    self.close_from(idx + 1);
+                    self.open_elements.pop();
+                }
+            }
+        }
+    }
+
+    /// Report the elements above `idx` in the stack as unclosed and drop them
+    fn close_from(&mut self, idx: usize) {
+        for (name, line, col) in self.open_elements.split_off(idx) {
+            if OPTIONAL_END_TAGS.contains(&&*name) {
+                continue;
+            }
+            self.report(line, col, format!("Element <{}> is not closed", name));
+            if !self
+                .unclosed_elements
+                .iter()
+                .any(|unclosed| **unclosed == *name)
+            {
+                self.unclosed_elements.push(name.to_string());
+            }
+        }
+    }
+
+    fn report(&mut self, line: u64, col: Option<u64>, message: String) {
+        self.messages.push(ValidationMessage { line, col, message });
+    }
+
+    /// Give the tree builder errors reported since the last call this column
+    fn errors_reported_at(&mut self, col: Option<u64>) {
+        let reported = self.tree_builder.sink.errors.len();
+        self.error_cols.resize(reported, col);
+    }
+}
+
+impl TokenSink for TagObserver<'_> {
+    type Handle = NodeRef;
+
+    fn process_token(&mut self, token: Token, line_number: u64) -> TokenSinkResult<NodeRef> {
+        let Token::TagToken(tag) = &token else {
+            let result = self.tree_builder.process_token(token, line_number);
+            self.errors_reported_at(None);
+            return result;
+        };
+        let col = self.locate(tag);
+        self.observe(tag, line_number, col);
+
+        // Tree builder errors only say "Unexpected token", so name the tag they were
+        // reported for
+        let description = match tag.kind {
+            TagKind::StartTag => format!("<{}>", tag.name),
+            TagKind::EndTag => format!("</{}>", tag.name),
+        };
+        let reported = self.tree_builder.sink.errors.len();
+        let result = self.tree_builder.process_token(token, line_number);
+        for error in &mut self.tree_builder.sink.errors[reported..] {
+            error.message = format!("{} ({})", error.message, description);
+        }
+        self.errors_reported_at(col);
+        result
+    }
+
+    fn end(&mut self) {
+        self.tree_builder.end();
+        self.errors_reported_at(None);
+        self.close_from(0);
+    }
+
+    fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+        self.tree_builder
+            .adjusted_current_node_present_but_not_in_html_namespace()
+    }
+}
+
+/// Parse an HTML document and report its parse errors and conformance problems
+pub fn validate_html(html: &str) -> ValidationReport {
+    let observer = TagObserver {
+        tree_builder: TreeBuilder::new(NodeSink::default(), TreeBuilderOpts::default()),
+        source: html,
+        cursor: 0,
+        raw_text: None,
+        error_cols: Vec::new(),
+        open_elements: Vec::new(),
+        ids: HashMap::new(),
+        messages: Vec::new(),
+        duplicate_ids: Vec::new(),
+        unclosed_elements: Vec::new(),
+    };
+    // Exact tokenizer errors say what was wrong ("Saw \" in state ..."), while exact tree
+    // builder errors only add debug dumps of tokens
+    let opts = TokenizerOpts {
+        exact_errors: true,
+        ..Default::default()
+    };
+    let mut tokenizer = Tokenizer::new(observer, opts);
+
+    let mut input = BufferQueue::new();
+    input.push_back(StrTendril::from(html));
+    while let TokenizerResult::Script(_) = tokenizer.feed(&mut input) {}
+    tokenizer.end();
+
+    let observer = tokenizer.sink;
+    let sink = observer.tree_builder.sink;
+    let mut messages: Vec<ValidationMessage> = sink
+        .errors
+        .into_iter()
+        .zip(observer.error_cols)
+        .map(|(error, col)| ValidationMessage {
+            line: error.line,
+            col,
+            message: error.message,
+        })
+        .chain(observer.messages)
+        .collect();
+    messages.sort_by_key(|message| (message.line, message.col));
+
+    let document = sink.document;
+    ValidationReport {
+        messages,
+        quirks_mode: document
+            .as_document()
+            .map(|document| document.quirks_mode())
+            .unwrap_or(QuirksMode::NoQuirks),
+        doctype: document
+            .children()
+            .find_map(|node| node.as_doctype().map(|doctype| doctype.name.clone())),
+        duplicate_ids: observer.duplicate_ids,
+        unclosed_elements: observer.unclosed_elements,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_document() {
+        let report = validate_html(
+            "<!DOCTYPE html>\n<html><head><title>Jobs</title></head>\n<body><ul><li>One<li>Two</ul><p>Text<br></body></html>",
+        );
+        assert_eq!(report.messages, vec![]);
+        assert_eq!(report.quirks_mode_name(), "no-quirks");
+        assert_eq!(report.doctype.as_deref(), Some("html"));
+    }
+
+    #[test]
+    fn test_missing_doctype_is_quirks() {
+        let report = validate_html("<p>Hi</p>");
+        assert_eq!(report.quirks_mode_name(), "quirks");
+        assert_eq!(report.doctype, None);
+        assert_eq!(report.messages.len(), 1);
+        assert_eq!(report.messages[0].line, 1);
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let report =
+            validate_html("<!DOCTYPE html>\n<div id=job></div>\n<span id=job></span><b id=x></b>");
+        assert_eq!(report.duplicate_ids, vec!["job"]);
+        assert!(report
+            .messages
+            .iter()
+            .any(|m| m.line == 3 && m.message == "Duplicate id 'job', first used on line 2"));
+    }
+
+    #[test]
+    fn test_unclosed_elements() {
+        let report = validate_html("<!DOCTYPE html>\n<div><p>One <b>bold</div>\n<section>");
+        assert_eq!(report.unclosed_elements, vec!["b", "section"]);
+        let lines: Vec<u64> = report
+            .messages
+            .iter()
+            .filter(|m| m.message.ends_with("is not closed"))
+            .map(|m| m.line)
+            .collect();
+        assert_eq!(lines, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_columns_of_tag_messages() {
+        let report = validate_html(
+            "<!DOCTYPE html>\n<div id=a>  <span id=a><b>x</div>\n<script>'</b>'</script> </p>",
+        );
+        let cols: Vec<(u64, Option<u64>, &str)> = report
+            .messages
+            .iter()
+            .map(|m| (m.line, m.col, m.message.as_str()))
+            .collect();
+        assert!(cols.contains(&(2, Some(13), "Duplicate id 'a', first used on line 2")));
+        assert!(cols.contains(&(2, Some(13), "Element <span> is not closed")));
+        assert!(cols.contains(&(2, Some(24), "Element <b> is not closed")));
+        // The </b> inside the script is text, the stray </p> is after it
+        assert!(cols.iter().any(|(line, col, message)| *line == 3
+            && *col == Some(25)
+            && message.ends_with("(</p>)")));
+    }
+
+    #[test]
+    fn test_tree_builder_errors_name_the_tag() {
+        let report = validate_html("<!DOCTYPE html>\n<p>One\n</div>");
+        assert_eq!(report.messages.len(), 1);
+        assert_eq!(report.messages[0].line, 3);
+        assert!(report.messages[0].message.ends_with("(</div>)"));
+    }
+
+    #[test]
+    fn test_tokenizer_errors_are_exact() {
+        let report = validate_html("<!DOCTYPE html><a href=x\"y>t</a>");
+        assert!(report
+            .messages
+            .iter()
+            .any(|m| m.message.contains("AttributeValue")));
+    }
+
+    #[test]
+    fn test_script_contents_are_not_tags() {
+        let report = validate_html("<!DOCTYPE html><script>if (a < b) { x = '<div>'; }</script>");
+        assert_eq!(report.unclosed_elements, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::sink::NodeSink;
+use html5ever::tendril::TendrilSink;
+use kuchikiki::NodeRef;
+use xml5ever::driver::{parse_document, XmlParseOpts};
+use xml5ever::serialize::{serialize, SerializeOpts, TraversalScope};
+
+/// Parse an XML document (feeds, sitemaps, SVG, XHTML) with xml5ever
+///
+/// Unlike the HTML parser this keeps the case of names, reads CDATA sections as text and
+/// doesn't treat elements like `<link>` as void.
+pub fn parse_xml(xml: &str) -> NodeRef {
+    parse_document(NodeSink::default(), XmlParseOpts::default())
+        .one(xml)
+        .document
+}
+
+/// Serialize a node and its descendants as XML
+pub fn to_xml_string(node: &NodeRef) -> String {
+    let mut content: Vec<u8> = Vec::new();
+    let opts = SerializeOpts {
+        traversal_scope: TraversalScope::IncludeNode,
+    };
+    if serialize(&mut content, node, opts).is_err() {
+        return String::new();
+    }
+    String::from_utf8(content).unwrap_or_default()
+}
+
+/// Element children of a node
+pub fn child_elements(node: &NodeRef) -> impl Iterator<Item = NodeRef> {
+    node.children().filter(|child| child.as_element().is_some())
+}
+
+/// Trimmed text of the first child element with the given local name and non-empty text
+///
+/// Names are matched regardless of namespace, so `date` finds `<dc:date>`.
+pub fn child_text(node: &NodeRef, name: &str) -> Option<String> {
+    child_elements(node)
+        .filter(|child| crate::name_of(child) == name)
+        .map(|child| child.text_contents().trim().to_string())
+        .find(|text| !text.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selector::select_nodes;
+    use crate::QueryOptions;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+<rss version="2.0" xmlns:media="http://search.yahoo.com/mrss/">
+  <channel>
+    <item>
+      <title><![CDATA[Senior <Rust> Engineer]]></title>
+      <link>https://example.com/jobs/1</link>
+      <pubDate>Mon, 06 Jan 2025 10:00:00 GMT</pubDate>
+      <media:content url="https://example.com/logo.png"/>
+    </item>
+  </channel>
+</rss>"#;
+
+    fn texts(xml: &str, selector: &str) -> Vec<String> {
+        let doc = parse_xml(xml);
+        select_nodes(&doc, selector, &QueryOptions::default())
+            .unwrap()
+            .iter()
+            .map(|node| node.text_contents())
+            .collect()
+    }
+
+    #[test]
+    fn test_link_keeps_text() {
+        assert_eq!(
+            texts(RSS, "item > link"),
+            vec!["https://example.com/jobs/1"]
+        );
+    }
+
+    #[test]
+    fn test_cdata_is_text() {
+        assert_eq!(texts(RSS, "item > title"), vec!["Senior <Rust> Engineer"]);
+    }
+
+    #[test]
+    fn test_case_is_preserved() {
+        assert_eq!(texts(RSS, "pubDate"), vec!["Mon, 06 Jan 2025 10:00:00 GMT"]);
+        assert!(texts(RSS, "pubdate").is_empty());
+    }
+
+    #[test]
+    fn test_declared_namespace_prefix() {
+        let doc = parse_xml(RSS);
+        let nodes = select_nodes(&doc, "media|content", &QueryOptions::default()).unwrap();
+        assert_eq!(nodes.len(), 1);
+        let attrs = nodes[0].as_element().unwrap().attributes.borrow();
+        assert_eq!(attrs.get("url"), Some("https://example.com/logo.png"));
+    }
+
+    #[test]
+    fn test_default_namespace() {
+        let atom =
+            r#"<feed xmlns="http://www.w3.org/2005/Atom"><entry><title>Job</title></entry></feed>"#;
+        assert_eq!(texts(atom, "entry > title"), vec!["Job"]);
+    }
+
+    #[test]
+    fn test_to_xml_string() {
+        let doc = parse_xml("<root><link>x</link><empty/></root>");
+        let link = doc.select_first("link").unwrap();
+        assert_eq!(to_xml_string(link.as_node()), "<link>x</link>");
+    }
+}